@@ -0,0 +1,58 @@
+use std::io::{self, BufRead, Write};
+
+/// A habit offered for ticking off, with whether it is already done today.
+pub struct Item {
+    pub label: String,
+    pub done: bool,
+}
+
+/// Lets the user toggle pending items by number until an empty line, then
+/// returns the indexes picked. `None` when cancelled with `q` or end of input
+/// before anything was confirmed.
+pub fn select(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    items: &[Item],
+) -> io::Result<Option<Vec<usize>>> {
+    let mut picked = vec![false; items.len()];
+    loop {
+        for (i, item) in items.iter().enumerate() {
+            let mark = if item.done {
+                "✓"
+            } else if picked[i] {
+                "x"
+            } else {
+                " "
+            };
+            writeln!(output, "  {:>2}. [{}] {}", i + 1, mark, item.label)?;
+        }
+        write!(
+            output,
+            "Numbers to tick or untick (e.g. 1 3), Enter to save, q to cancel: "
+        )?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Some((0..items.len()).filter(|i| picked[*i]).collect()));
+        }
+        if line.eq_ignore_ascii_case("q") {
+            return Ok(None);
+        }
+        for word in line.split([' ', ',']).filter(|w| !w.is_empty()) {
+            match word.parse::<usize>() {
+                Ok(n) if (1..=items.len()).contains(&n) && !items[n - 1].done => {
+                    picked[n - 1] = !picked[n - 1];
+                }
+                Ok(n) if (1..=items.len()).contains(&n) => {
+                    writeln!(output, "  {} is already done today", items[n - 1].label)?;
+                }
+                _ => writeln!(output, "  No habit number {}", word)?,
+            }
+        }
+    }
+}
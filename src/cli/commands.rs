@@ -1,15 +1,54 @@
+use crate::cli::checkin::{self, Item};
+use crate::cli::heatmap::{self, Style};
+use crate::cli::output::{Format, Output};
+use crate::cli::report::{self, Layout, Period};
+use crate::cli::table::{self, Column, SortKey};
+use crate::cli::views::{
+    BackupView, CalendarDayView, CandidateView, ChangeView, CompletionView, ConfigView, ExportView,
+    HabitRef, HabitView, ImportView, IssueView, JournalEntryView, MigrationView, PauseView,
+    RecoveryView, RestoreView, StatsView, StreakRow, StreakView, UncompleteView, VacationView,
+    VerifyView,
+};
+use crate::config::Config;
 use crate::error::{HabitError, Result};
-use crate::models::habit::Habit;
+use crate::models::analytics::{Direction, HabitStats};
+use crate::models::habit::{Habit, Pause, day_instant, goal_from};
+use crate::models::schedule::{PeriodKind, Schedule};
+use crate::models::timezone::Zone;
+use crate::storage::backend::{Backend, StorageLocation};
+use crate::storage::backup::{BackupEntry, Backups};
+use crate::storage::diff;
+use crate::storage::journal::{EntryKind, Event, History, Journal};
 use crate::storage::json_storage::HabitStore;
-use chrono::Utc;
+use crate::storage::recovery::{self, RecoverySource};
+use crate::storage::repository::HabitRepository;
+use crate::storage::validation::Severity;
+use crate::transfer::csv::{self, COMPLETION_FIELDS, ColumnMap, FIELDS};
+use crate::transfer::ics;
+use crate::transfer::import::{self, MergeBy, Source};
+use crate::utils::{day_range, parse_day};
+use chrono::{Days, Months, NaiveDate, Utc};
 use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 #[derive(Debug, Parser)]
 #[command(name = "habit", version, about = "Habit Tracker CLI")]
 pub struct Cli {
+    /// Timezone for day boundaries (IANA name or offset); overrides TZ and the stored setting
+    #[arg(long, global = true)]
+    pub tz: Option<String>,
+    /// Output format: text, json, csv or tsv (and ics for export)
+    #[arg(long, global = true, default_value_t = Format::Text)]
+    pub format: Format,
     #[command(subcommand)]
     pub command: Commands,
+    /// The command line as typed, recorded in the journal
+    #[arg(skip)]
+    pub invocation: String,
 }
 
 #[derive(Debug, Subcommand)]
@@ -19,16 +58,138 @@ pub enum Commands {
         name: String,
         #[arg(long)]
         description: Option<String>,
+        /// daily, mon,wed,fri, 3/week, every 2 days, 4/month or days 1,15
+        #[arg(long, conflicts_with = "frequency")]
+        schedule: Option<Schedule>,
+        /// Target days per week (shorthand for --schedule N/week)
         #[arg(long)]
         frequency: Option<u32>,
+        /// Daily amount to reach for a measurable habit, e.g. 8
+        #[arg(long)]
+        target: Option<f64>,
+        /// Unit of the daily target, e.g. glasses or km
+        #[arg(long, requires = "target")]
+        unit: Option<String>,
+        /// Tag the habit (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
     },
     /// List habits
     List {
-        #[arg(long, default_value_t = true)]
+        /// Only active habits (the default)
+        #[arg(long, conflicts_with_all = ["inactive", "all"])]
         active: bool,
+        /// Only deactivated habits
+        #[arg(long, conflicts_with = "all")]
+        inactive: bool,
+        /// Active and deactivated habits
+        #[arg(long)]
+        all: bool,
+        /// Only habits still due today
+        #[arg(long, conflicts_with = "done_today")]
+        due_today: bool,
+        /// Only habits already done today
+        #[arg(long)]
+        done_today: bool,
+        /// Only habits with this tag (repeatable; all must match)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Only habits whose name or description contains this text
+        #[arg(long)]
+        search: Option<String>,
+        /// Order by name, created, streak, rate or due
+        #[arg(long)]
+        sort: Option<SortKey>,
+        /// Table columns, e.g. name,today,streak (id, name, schedule, today,
+        /// streak, rate, progress, created, tags, description)
+        #[arg(long, value_delimiter = ',', conflicts_with = "long")]
+        columns: Vec<Column>,
+        /// Show every detail of each habit instead of a table
+        #[arg(long)]
+        long: bool,
+    },
+    /// Show what is done and still due today
+    Today {
+        /// Tick off several habits in one go
+        #[arg(long, short)]
+        interactive: bool,
+        /// Include active habits that are not scheduled today
+        #[arg(long)]
+        all: bool,
+    },
+    /// Mark habit complete for today or past days
+    Complete {
+        identifier: String,
+        /// Day to log: YYYY-MM-DD, today, yesterday or relative like -2d
+        #[arg(long, allow_hyphen_values = true, conflicts_with_all = ["yesterday", "from"])]
+        date: Option<String>,
+        /// Log yesterday instead of today
+        #[arg(long, conflicts_with = "from")]
+        yesterday: bool,
+        /// First day of a range to log
+        #[arg(long, allow_hyphen_values = true, requires = "to")]
+        from: Option<String>,
+        /// Last day of a range to log (inclusive)
+        #[arg(long, allow_hyphen_values = true, requires = "from")]
+        to: Option<String>,
+        /// Allow future days and days before the habit was created
+        #[arg(long)]
+        force: bool,
+        /// Amount to add towards a measurable habit's daily target
+        #[arg(long, conflicts_with = "from")]
+        amount: Option<f64>,
+        /// Note to keep with the check-in
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Remove a completion logged by mistake
+    Uncomplete {
+        identifier: String,
+        /// Day to revert: YYYY-MM-DD, today, yesterday or relative like -2d
+        #[arg(long, allow_hyphen_values = true)]
+        date: Option<String>,
+    },
+    /// Rank habits by streak
+    Streaks {
+        /// Show the full streak history of a single habit
+        identifier: Option<String>,
+        #[arg(long)]
+        history: bool,
+    },
+    /// Completion rates, trends and habits of timing
+    Stats {
+        /// Show one habit in detail instead of a table of all active habits
+        identifier: Option<String>,
+    },
+    /// Scheduled vs completed per habit for a week or month
+    Report {
+        /// ISO week such as 2026-W41, or 'last' [default: this week]
+        #[arg(long, conflicts_with = "month")]
+        week: Option<Option<String>>,
+        /// Month such as 2026-10, or 'last'
+        #[arg(long)]
+        month: Option<Option<String>>,
+        /// Render the report as a Markdown table
+        #[arg(long, conflicts_with = "html")]
+        markdown: bool,
+        /// Render the report as an HTML fragment
+        #[arg(long)]
+        html: bool,
+    },
+    /// Heatmap of completions with a column per week
+    Calendar {
+        /// Show one habit instead of all active habits
+        identifier: Option<String>,
+        /// Calendar year to show
+        #[arg(long, conflicts_with = "months")]
+        year: Option<i32>,
+        /// Months to show, ending with the current one [default: 12]
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..=120))]
+        months: Option<u32>,
+        /// Plain ASCII cells, without color or unicode
+        #[arg(long)]
+        ascii: bool,
     },
-    /// Mark habit complete for today
-    Complete { identifier: String },
     /// Remove habit
     Remove { identifier: String },
     /// Edit habit details
@@ -38,92 +199,710 @@ pub enum Commands {
         name: Option<String>,
         #[arg(long)]
         description: Option<String>,
+        #[arg(long, conflicts_with = "frequency")]
+        schedule: Option<Schedule>,
+        /// Target days per week, or 'null' for daily
         #[arg(long)]
         frequency: Option<String>,
+        /// Daily target amount, or 'null' to make it a plain done/not-done habit
+        #[arg(long)]
+        target: Option<String>,
+        /// Unit of the daily target
+        #[arg(long)]
+        unit: Option<String>,
         #[arg(long)]
         active: Option<bool>,
+        /// Add a tag (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Remove a tag (repeatable)
+        #[arg(long = "untag")]
+        untags: Vec<String>,
+    },
+    /// Put a habit on hold; paused days count as neither missed nor done
+    Pause {
+        identifier: String,
+        /// First paused day [default: today]
+        #[arg(long, allow_hyphen_values = true)]
+        from: Option<String>,
+        /// Last paused day (inclusive) [default: until `habit resume`]
+        #[arg(long, allow_hyphen_values = true)]
+        until: Option<String>,
+    },
+    /// End a habit's pause today and drop any planned later
+    Resume { identifier: String },
+    /// Pause every habit for a date range
+    Vacation {
+        #[command(subcommand)]
+        action: VacationCommand,
+    },
+    /// Manage the storage backend
+    Storage {
+        #[command(subcommand)]
+        action: StorageCommand,
+    },
+    /// List, take, or restore store backups
+    Backup {
+        #[command(subcommand)]
+        action: BackupCommand,
+    },
+    /// Write habits and completions as CSV (or TSV/JSON with --format)
+    Export {
+        /// File to write [default: stdout]
+        path: Option<PathBuf>,
+        /// Write completions to this file and only habits to PATH
+        #[arg(long, requires = "path")]
+        completions: Option<PathBuf>,
+    },
+    /// Add habits and completions from CSV files or other apps' exports
+    Import {
+        /// Files to read in order, e.g. habits before their completions
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// What the files are: csv, loop, habitica or date-habit
+        #[arg(long, default_value_t = Source::Csv)]
+        from: Source,
+        /// Read a CSV column as an import field, e.g. --map Day=date (repeatable)
+        #[arg(long = "map")]
+        mappings: Vec<ColumnMap>,
+        /// Match existing habits by name or id
+        #[arg(long = "merge", default_value_t = MergeBy::Name)]
+        merge_by: MergeBy,
+        /// Show what would change without saving
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Revert the last N changes
+    Undo {
+        #[arg(default_value_t = 1)]
+        count: usize,
+    },
+    /// Reapply the last N undone changes
+    Redo {
+        #[arg(default_value_t = 1)]
+        count: usize,
+    },
+    /// Show recorded changes, newest first
+    Log {
+        /// Number of entries to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Check that replaying the journal rebuilds the current store
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Repair a damaged store from a temp file, the journal, a backup, or what still parses
+    Recover {
+        /// Where to recover from: temp, journal, backup or salvage (default: best available)
+        #[arg(long)]
+        source: Option<RecoverySource>,
+        /// Replace the damaged store instead of only previewing the recovery
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Check the store for integrity problems
+    Doctor {
+        /// Repair every problem found
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Full-screen interface: habit list, calendar and stats
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Show or change stored settings
+    Config {
+        /// IANA timezone (e.g. Asia/Kolkata) or offset (e.g. +05:30), or 'null' to clear
+        #[arg(long)]
+        timezone: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StorageCommand {
+    /// Copy every habit and completion into another backend
+    Migrate {
+        /// Target backend: json or sqlite
+        #[arg(long)]
+        to: Backend,
+        /// Target file (defaults to the current store with a .json or .db extension)
+        #[arg(long)]
+        path: Option<PathBuf>,
+        /// Overwrite a target that already holds habits
+        #[arg(long)]
+        force: bool,
     },
 }
 
-pub fn run(cli: Cli) -> Result<()> {
+#[derive(Debug, Subcommand)]
+pub enum BackupCommand {
+    /// Show available backups, newest first
+    List,
+    /// Take a backup of the current store now
+    Create,
+    /// Replace the store with a backup (previews the changes without --yes)
+    Restore {
+        /// Backup number from `backup list` or a timestamp prefix (e.g. 20250301T14)
+        id: String,
+        /// Apply the restore instead of only previewing it
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VacationCommand {
+    /// Pause every habit, including ones added later, from one day to another
+    Add {
+        /// First day away [default: today]
+        #[arg(long, allow_hyphen_values = true)]
+        from: Option<String>,
+        /// Last day away (inclusive)
+        #[arg(long, allow_hyphen_values = true)]
+        to: String,
+    },
+    /// Show vacations, earliest first
+    List,
+    /// Take a vacation back, un-pausing its days for every habit
+    Remove {
+        /// The vacation's first day
+        #[arg(allow_hyphen_values = true)]
+        from: String,
+    },
+}
+
+pub fn run(cli: Cli, repo: &mut dyn HabitRepository) -> Result<()> {
+    // only `export` writes a calendar; refuse it before anything changes
+    if cli.format == Format::Ics && !matches!(cli.command, Commands::Export { .. }) {
+        return Err(HabitError::UnsupportedFormat("ics".into()));
+    }
+    // held until the command returns, covering the whole load–modify–save cycle
+    let _lock = repo.lock()?;
+    let out = Output::new(cli.format);
+    // the interface takes the lock for each change instead, so other
+    // commands can run while it is open
+    #[cfg(feature = "tui")]
+    if let Commands::Tui = cli.command {
+        drop(_lock);
+        let stored = repo.load()?.settings.timezone;
+        return crate::tui::terminal::run(repo, Zone::resolve(cli.tz.as_deref(), stored)?);
+    }
+    // the store may not load at all, so recovery runs before anything reads it
+    if let Commands::Recover { source, yes } = cli.command {
+        return recover(repo, source, yes, out);
+    }
+    let before = repo.load()?;
+    let journal = repo.location().map(Journal::for_store);
+    // undo and redo journal their own entries
+    let journaled = !matches!(
+        cli.command,
+        Commands::Undo { .. } | Commands::Redo { .. } | Commands::Log { .. }
+    );
+    let invocation = cli.invocation.clone();
+    execute(cli, repo, before.clone(), journal.as_ref(), out)?;
+    if let Some(journal) = journal.filter(|_| journaled) {
+        journal.record(&invocation, &before, &repo.load()?)?;
+    }
+    Ok(())
+}
+
+fn execute(
+    cli: Cli,
+    repo: &mut dyn HabitRepository,
+    mut store: HabitStore,
+    journal: Option<&Journal>,
+    out: Output,
+) -> Result<()> {
+    let zone = Zone::resolve(cli.tz.as_deref(), store.settings.timezone)?;
+    let today = zone.today();
     match cli.command {
         Commands::Add {
             name,
             description,
+            schedule,
             frequency,
+            target,
+            unit,
+            tags,
         } => {
             if name.trim().is_empty() {
                 return Err(HabitError::InvalidName(name));
             }
-            let mut store = HabitStore::load()?;
-            let habit = Habit::new(name, description, frequency);
-            let id = habit.id;
-            let title = habit.name.clone();
+            let goal = target
+                .map(|t| goal_from(t, unit.unwrap_or_default()))
+                .transpose()?;
+            let schedule = schedule
+                .or_else(|| frequency.map(Schedule::from_weekly_target))
+                .unwrap_or_default();
+            let mut habit = Habit::new(name, description, schedule);
+            habit.goal = goal;
+            habit.add_tags(tags)?;
+            // vacation mode covers every habit, new ones included
+            habit.pauses = store
+                .vacations()
+                .into_iter()
+                .filter(|v| v.until.is_none_or(|until| until >= today))
+                .collect();
+            let view = HabitView::new(&habit, today, &zone);
             store.habits.push(habit);
-            store.save()?;
-            println!("  Added habit: '{}' (ID: {})", title, id);
-            Ok(())
+            repo.save(&store)?;
+            out.emit(&view, || {
+                println!("  Added habit: '{}' (ID: {})", view.name, view.id)
+            })
         }
-        Commands::List { active } => {
-            let store = HabitStore::load()?;
-            let mut any = false;
-            for h in store.habits.iter().filter(|h| !active || h.is_active) {
-                any = true;
-                println!("ID: {} | {}", h.id, h.name);
-                if let Some(desc) = &h.description {
-                    println!("  Description: {}", desc);
+        Commands::List {
+            active: _,
+            inactive,
+            all,
+            due_today,
+            done_today,
+            tags,
+            search,
+            sort,
+            columns,
+            long,
+        } => {
+            let search = search.map(|s| s.to_lowercase());
+            let mut views: Vec<HabitView> = store
+                .habits
+                .iter()
+                .filter(|h| all || h.is_active != inactive)
+                .filter(|h| tags.iter().all(|t| h.has_tag(t)))
+                .filter(|h| {
+                    search.as_ref().is_none_or(|s| {
+                        h.name.to_lowercase().contains(s)
+                            || h.description
+                                .as_ref()
+                                .is_some_and(|d| d.to_lowercase().contains(s))
+                    })
+                })
+                .map(|h| HabitView::new(h, today, &zone))
+                .filter(|v| !due_today || v.due_today)
+                .filter(|v| !done_today || v.done_today)
+                .collect();
+            if let Some(sort) = sort {
+                sort.sort(&mut views);
+            }
+            out.rows(&views, || {
+                if views.is_empty() {
+                    println!("  No habits to display");
+                } else if long {
+                    for h in &views {
+                        print_long(h);
+                    }
+                } else {
+                    let columns = if columns.is_empty() {
+                        Column::DEFAULT.to_vec()
+                    } else {
+                        columns
+                    };
+                    let headers: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+                    let rows: Vec<Vec<String>> = views
+                        .iter()
+                        .map(|h| columns.iter().map(|c| c.cell(h)).collect())
+                        .collect();
+                    println!("{}", table::render(&headers, &rows));
                 }
-                println!("  Created: {}", h.created_at.date_naive());
-                println!("  Completions: {}/{} days", h.completions.len(), h.target_frequency.unwrap_or(0));
-                if let Some(freq) = h.target_frequency {
-                    println!("  Target: {} days", freq);
+            })
+        }
+        Commands::Today { interactive, all } => {
+            let views: Vec<HabitView> = store
+                .habits
+                .iter()
+                .filter(|h| h.is_active)
+                .map(|h| HabitView::new(h, today, &zone))
+                .filter(|v| all || v.due_today || v.done_today)
+                .collect();
+            if !interactive {
+                return out.rows(&views, || print_today(&views, today));
+            }
+            let items: Vec<Item> = views
+                .iter()
+                .map(|v| Item {
+                    label: match (v.target, &v.unit) {
+                        (Some(target), Some(unit)) => {
+                            format!("{} ({}/{} {})", v.name, v.today_total, target, unit)
+                        }
+                        _ => v.name.clone(),
+                    },
+                    done: v.done_today,
+                })
+                .collect();
+            // prompts stay off stdout when it carries machine-readable output
+            let mut input = std::io::stdin().lock();
+            let picked = if items.iter().all(|i| i.done) {
+                Some(Vec::new())
+            } else if out.is_text() {
+                checkin::select(&mut input, &mut std::io::stdout(), &items)?
+            } else {
+                checkin::select(&mut input, &mut std::io::stderr(), &items)?
+            };
+            let Some(picked) = picked else {
+                return out.rows::<CompletionView>(&[], || println!("Cancelled; nothing saved"));
+            };
+            let mut ticked = Vec::new();
+            for i in picked {
+                let Some(habit) = store.habits.iter_mut().find(|h| h.id == views[i].id) else {
+                    continue;
+                };
+                if habit.mark_complete(day_instant(today, today, &zone), &zone) {
+                    ticked.push(CompletionView {
+                        id: habit.id,
+                        name: habit.name.clone(),
+                        added: vec![today],
+                        already_completed: Vec::new(),
+                        amount: None,
+                        day_total: None,
+                        target: None,
+                    });
                 }
-                println!("  Active: {}", h.is_active);
             }
-            if !any {
-                println!("  No habits to display (active = {})", active);
+            if !ticked.is_empty() {
+                repo.save(&store)?;
             }
-            Ok(())
+            out.rows(&ticked, || {
+                if items.iter().all(|i| i.done) {
+                    println!("🎉 Everything due today is already done");
+                } else if ticked.is_empty() {
+                    println!("Nothing ticked; nothing saved");
+                }
+                for c in &ticked {
+                    println!("✅ Marked complete: '{}' (today)", c.name);
+                }
+            })
         }
-        Commands::Complete { identifier } => {
-            let mut store = HabitStore::load()?;
+        Commands::Complete {
+            identifier,
+            date,
+            yesterday,
+            from,
+            to,
+            force,
+            amount,
+            note,
+        } => {
             let Some(habit) = store.find_by_ident_mut(&identifier) else {
                 return Err(HabitError::NotFound(identifier));
             };
-            let ok = habit.mark_complete(Utc::now());
-            if !ok {
-                return Err(HabitError::AlreadyCompleted(habit.name.clone()));
+            let days = match (from, to) {
+                (Some(from), Some(to)) => {
+                    day_range(parse_day(&from, today)?, parse_day(&to, today)?)?
+                }
+                _ if yesterday => vec![today - Days::new(1)],
+                _ => vec![match date {
+                    Some(d) => parse_day(&d, today)?,
+                    None => today,
+                }],
+            };
+            for day in &days {
+                habit.check_loggable(*day, today, &zone, force)?;
             }
-            let name = habit.name.clone();
-            store.save()?;
-            println!("✅ Marked complete: '{}' (today)", name);
-            Ok(())
+            if let Some(day) = days.iter().find(|d| habit.is_paused(**d)) {
+                out.note(format!(
+                    "⏸️  '{}' is paused on {}; check-ins then do not count towards streaks or rates",
+                    habit.name, day
+                ));
+            }
+            if let Some(amount) = amount {
+                let Some(goal) = habit.goal.clone() else {
+                    return Err(HabitError::InvalidAmount(format!(
+                        "'{}' has no daily target",
+                        habit.name
+                    )));
+                };
+                if !(amount > 0.0 && amount.is_finite()) {
+                    return Err(HabitError::InvalidAmount(amount.to_string()));
+                }
+                let day = days[0];
+                let at = day_instant(day, today, &zone);
+                let total = habit.log_amount(at, amount, &zone);
+                if let Some(note) = &note {
+                    habit.set_note(at, note);
+                }
+                let view = CompletionView {
+                    id: habit.id,
+                    name: habit.name.clone(),
+                    added: vec![day],
+                    already_completed: Vec::new(),
+                    amount: Some(amount),
+                    day_total: Some(total),
+                    target: Some(goal.target),
+                };
+                repo.save(&store)?;
+                return out.emit(&view, || {
+                    println!(
+                        "✅ Logged {} {} for '{}' ({}/{} {} on {})",
+                        amount, goal.unit, view.name, total, goal.target, goal.unit, day
+                    );
+                    if total >= goal.target && total - amount < goal.target {
+                        println!("🎯 Daily target reached!");
+                    }
+                });
+            }
+            let (mut added, mut present) = (Vec::new(), Vec::new());
+            for day in &days {
+                let at = day_instant(*day, today, &zone);
+                if habit.mark_complete(at, &zone) {
+                    if let Some(note) = &note {
+                        habit.set_note(at, note);
+                    }
+                    added.push(*day);
+                } else {
+                    present.push(*day);
+                }
+            }
+            let view = CompletionView {
+                id: habit.id,
+                name: habit.name.clone(),
+                added,
+                already_completed: present,
+                amount: None,
+                day_total: None,
+                target: None,
+            };
+            if let [day] = days[..] {
+                if view.added.is_empty() {
+                    return Err(HabitError::AlreadyCompleted(format!(
+                        "{} ({})",
+                        day, view.name
+                    )));
+                }
+                repo.save(&store)?;
+                let when = if day == today {
+                    "today".to_string()
+                } else {
+                    day.to_string()
+                };
+                return out.emit(&view, || {
+                    println!("✅ Marked complete: '{}' ({})", view.name, when)
+                });
+            }
+            if !view.added.is_empty() {
+                repo.save(&store)?;
+            }
+            out.emit(&view, || {
+                println!(
+                    "✅ '{}': {} day(s) newly completed, {} already present",
+                    view.name,
+                    view.added.len(),
+                    view.already_completed.len()
+                );
+                for day in &view.added {
+                    println!("  + {}", day);
+                }
+                for day in &view.already_completed {
+                    println!("  = {} (already completed)", day);
+                }
+            })
+        }
+        Commands::Uncomplete { identifier, date } => {
+            let Some(habit) = store.find_by_ident_mut(&identifier) else {
+                return Err(HabitError::NotFound(identifier));
+            };
+            let day = match date {
+                Some(d) => parse_day(&d, today)?,
+                None => today,
+            };
+            if !habit.unmark_complete(day_instant(day, today, &zone), &zone) {
+                return Err(HabitError::NotCompleted(format!(
+                    "{} ({})",
+                    day, habit.name
+                )));
+            }
+            let view = UncompleteView {
+                id: habit.id,
+                name: habit.name.clone(),
+                date: day,
+            };
+            repo.save(&store)?;
+            out.emit(&view, || {
+                println!("↩️  Removed completion: '{}' ({})", view.name, day)
+            })
+        }
+        Commands::Streaks {
+            identifier,
+            history,
+        } => {
+            let habits: Vec<&Habit> = match &identifier {
+                Some(ident) => match store.find_by_ident(ident) {
+                    Some(h) => vec![h],
+                    None => return Err(HabitError::NotFound(ident.clone())),
+                },
+                None => store.habits.iter().filter(|h| h.is_active).collect(),
+            };
+            let mut ranked: Vec<_> = habits
+                .into_iter()
+                .map(|h| (h, h.streaks(today, &zone)))
+                .collect();
+            ranked.sort_by(|(a, sa), (b, sb)| {
+                sb.current
+                    .cmp(&sa.current)
+                    .then(sb.longest.cmp(&sa.longest))
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+            let show_history = history || identifier.is_some();
+            let rows: Vec<StreakRow> = ranked
+                .iter()
+                .enumerate()
+                .map(|(rank, (h, streak))| StreakRow {
+                    rank: rank + 1,
+                    id: h.id,
+                    name: h.name.clone(),
+                    streak: StreakView::from(streak),
+                    history: show_history.then(|| streak.history.clone()),
+                })
+                .collect();
+            out.rows(&rows, || {
+                if ranked.is_empty() {
+                    println!("  No habits to display");
+                }
+                for (rank, (h, streak)) in ranked.iter().enumerate() {
+                    println!(
+                        "{:>3}. {} — 🔥 {} {} (longest {}){}",
+                        rank + 1,
+                        h.name,
+                        streak.current,
+                        streak.unit.label(streak.current),
+                        streak.longest,
+                        if streak.at_risk {
+                            " ⚠️  at risk today"
+                        } else {
+                            ""
+                        }
+                    );
+                    if show_history {
+                        for run in streak.history.iter().rev() {
+                            println!(
+                                "       {} → {}: {} {}",
+                                run.start,
+                                run.end,
+                                run.length,
+                                streak.unit.label(run.length)
+                            );
+                        }
+                    }
+                }
+            })
+        }
+        Commands::Stats { identifier } => {
+            let habits: Vec<&Habit> = match &identifier {
+                Some(ident) => match store.find_by_ident(ident) {
+                    Some(h) => vec![h],
+                    None => return Err(HabitError::NotFound(ident.clone())),
+                },
+                None => store.habits.iter().filter(|h| h.is_active).collect(),
+            };
+            let views: Vec<StatsView> = habits
+                .iter()
+                .map(|h| StatsView {
+                    id: h.id,
+                    name: h.name.clone(),
+                    schedule: h.schedule.to_string(),
+                    stats: HabitStats::for_habit(h, today, &zone),
+                })
+                .collect();
+            out.rows(&views, || match (&identifier, views.first()) {
+                (Some(_), Some(view)) => print_stats(view),
+                _ => print_stats_table(&views),
+            })
+        }
+        Commands::Report {
+            week,
+            month,
+            markdown,
+            html,
+        } => {
+            let period = match (week, month) {
+                (_, Some(month)) => Period::month(month.as_deref(), today)?,
+                (week, None) => Period::week(week.flatten().as_deref(), today)?,
+            };
+            let rows = report::rows(&store.habits, period, today, &zone);
+            let layout = match (markdown, html) {
+                (true, _) => Layout::Markdown,
+                (_, true) => Layout::Html,
+                _ => Layout::Text,
+            };
+            out.rows(&rows, || {
+                println!("{}", report::render(period, &rows, layout))
+            })
+        }
+        Commands::Calendar {
+            identifier,
+            year,
+            months,
+            ascii,
+        } => {
+            let habits: Vec<&Habit> = match &identifier {
+                Some(ident) => match store.find_by_ident(ident) {
+                    Some(h) => vec![h],
+                    None => return Err(HabitError::NotFound(ident.clone())),
+                },
+                None => store.habits.iter().filter(|h| h.is_active).collect(),
+            };
+            let (from, to) = match year {
+                Some(year) => NaiveDate::from_ymd_opt(year, 1, 1)
+                    .zip(NaiveDate::from_ymd_opt(year, 12, 31))
+                    .ok_or_else(|| HabitError::InvalidDate(year.to_string()))?,
+                None => {
+                    let months = months.unwrap_or(12) - 1;
+                    (
+                        PeriodKind::Month.start_of(today) - Months::new(months),
+                        today,
+                    )
+                }
+            };
+            let ratios = heatmap::day_ratios(&habits, from, to, today, &zone);
+            let days: Vec<CalendarDayView> = ratios
+                .iter()
+                .filter(|(day, _)| **day <= today)
+                .map(|(day, ratio)| CalendarDayView {
+                    date: *day,
+                    ratio: *ratio,
+                    level: ratio.map(heatmap::level),
+                })
+                .collect();
+            out.rows(&days, || {
+                let title = match &identifier {
+                    Some(_) => habits[0].name.as_str(),
+                    None => "All active habits",
+                };
+                println!("{}, {} to {}", title, from, to);
+                let style = if ascii { Style::Ascii } else { Style::detect() };
+                println!("{}", heatmap::render(&ratios, style));
+            })
         }
         Commands::Remove { identifier } => {
-            let mut store = HabitStore::load()?;
-            let before = store.habits.len();
-            store.habits.retain(|h| {
+            let matches = |h: &Habit| {
                 if let Ok(id) = identifier.parse::<Uuid>() {
-                    h.id != id
+                    h.id == id
                 } else {
-                    !h.name.eq_ignore_ascii_case(&identifier)
+                    h.has_name(&identifier)
                 }
-            });
-            if store.habits.len() == before {
+            };
+            let removed: Vec<HabitRef> = store
+                .habits
+                .iter()
+                .filter(|h| matches(h))
+                .map(HabitRef::from)
+                .collect();
+            if removed.is_empty() {
                 return Err(HabitError::NotFound(identifier));
             }
-            store.save()?;
-            println!("🗑️  Removed habit: {}", identifier);
-            Ok(())
+            store.habits.retain(|h| !matches(h));
+            repo.save(&store)?;
+            out.rows(&removed, || println!("🗑️  Removed habit: {}", identifier))
         }
         Commands::Edit {
             identifier,
             name,
             description,
+            schedule,
             frequency,
+            target,
+            unit,
             active,
+            tags,
+            untags,
         } => {
-            let mut store = HabitStore::load()?;
             let Some(habit) = store.find_by_ident_mut(&identifier) else {
                 return Err(HabitError::NotFound(identifier));
             };
@@ -140,23 +919,966 @@ pub fn run(cli: Cli) -> Result<()> {
                     habit.description = Some(desc);
                 }
             }
+            if let Some(schedule) = schedule {
+                habit.schedule = schedule;
+            }
             if let Some(freq_str) = frequency {
                 if freq_str.eq_ignore_ascii_case("null") {
-                    habit.target_frequency = None;
+                    habit.schedule = Schedule::Daily;
                 } else {
                     let parsed: u32 = freq_str
                         .parse()
-                        .map_err(|_| HabitError::InvalidName("frequency".into()))?;
-                    habit.target_frequency = Some(parsed);
+                        .map_err(|_| HabitError::InvalidSchedule(freq_str.clone()))?;
+                    habit.schedule = Schedule::from_weekly_target(parsed);
+                }
+            }
+            if let Some(t) = target {
+                habit.goal = if t.eq_ignore_ascii_case("null") {
+                    None
+                } else {
+                    let t: f64 = t
+                        .parse()
+                        .map_err(|_| HabitError::InvalidAmount(t.clone()))?;
+                    let unit = unit
+                        .clone()
+                        .or_else(|| habit.goal.as_ref().map(|g| g.unit.clone()))
+                        .unwrap_or_default();
+                    Some(goal_from(t, unit)?)
+                };
+            } else if let Some(unit) = unit {
+                match habit.goal.as_mut() {
+                    Some(goal) => goal.unit = unit,
+                    None => {
+                        return Err(HabitError::InvalidAmount(format!(
+                            "'{}' has no daily target",
+                            habit.name
+                        )));
+                    }
                 }
             }
             if let Some(is_active) = active {
                 habit.is_active = is_active;
             }
-            let name_out = habit.name.clone();
-            store.save()?;
-            println!("✏️  Updated habit: '{}'", name_out);
+            habit.add_tags(tags)?;
+            habit
+                .tags
+                .retain(|t| !untags.iter().any(|u| u.eq_ignore_ascii_case(t)));
+            let view = HabitView::new(habit, today, &zone);
+            repo.save(&store)?;
+            out.emit(&view, || println!("✏️  Updated habit: '{}'", view.name))
+        }
+        Commands::Pause {
+            identifier,
+            from,
+            until,
+        } => {
+            let Some(habit) = store.find_by_ident_mut(&identifier) else {
+                return Err(HabitError::NotFound(identifier));
+            };
+            let from = match from {
+                Some(d) => parse_day(&d, today)?,
+                None => today,
+            };
+            let until = until.map(|d| parse_day(&d, today)).transpose()?;
+            if let Some(until) = until
+                && until < from
+            {
+                return Err(HabitError::InvalidDate(format!(
+                    "{} is after {}",
+                    from, until
+                )));
+            }
+            let pause = Pause {
+                from,
+                until,
+                vacation: false,
+            };
+            if let Some(day) = habit.pauses.iter().find_map(|p| p.overlap(&pause)) {
+                return Err(HabitError::AlreadyPaused(format!(
+                    "{} ({})",
+                    day, habit.name
+                )));
+            }
+            habit.pauses.push(pause);
+            habit.pauses.sort_by_key(|p| p.from);
+            let view = PauseView::new(habit, today);
+            repo.save(&store)?;
+            out.emit(&view, || {
+                println!(
+                    "⏸️  Paused '{}' from {} {}",
+                    view.name,
+                    from,
+                    until.map_or("until resumed".into(), |u| format!("to {}", u))
+                )
+            })
+        }
+        Commands::Resume { identifier } => {
+            let Some(habit) = store.find_by_ident_mut(&identifier) else {
+                return Err(HabitError::NotFound(identifier));
+            };
+            if !habit.resume(today) {
+                return Err(HabitError::NotPaused(habit.name.clone()));
+            }
+            let view = PauseView::new(habit, today);
+            repo.save(&store)?;
+            out.emit(&view, || println!("▶️  Resumed '{}' from today", view.name))
+        }
+        Commands::Vacation { action } => match action {
+            VacationCommand::Add { from, to } => {
+                let from = match from {
+                    Some(d) => parse_day(&d, today)?,
+                    None => today,
+                };
+                let until = parse_day(&to, today)?;
+                if until < from {
+                    return Err(HabitError::InvalidDate(format!(
+                        "{} is after {}",
+                        from, until
+                    )));
+                }
+                let vacation = Pause {
+                    from,
+                    until: Some(until),
+                    vacation: true,
+                };
+                if store.vacations().contains(&vacation) {
+                    return Err(HabitError::AlreadyPaused(format!(
+                        "vacation from {} to {}",
+                        from, until
+                    )));
+                }
+                for habit in &mut store.habits {
+                    habit.pauses.push(vacation);
+                    habit.pauses.sort_by_key(|p| p.from);
+                }
+                repo.save(&store)?;
+                let view = VacationView::new(&store, vacation);
+                out.emit(&view, || {
+                    println!(
+                        "🏖️  Vacation from {} to {}: {} habits paused",
+                        from, until, view.habits
+                    )
+                })
+            }
+            VacationCommand::List => {
+                let views: Vec<VacationView> = store
+                    .vacations()
+                    .into_iter()
+                    .map(|v| VacationView::new(&store, v))
+                    .collect();
+                out.rows(&views, || {
+                    if views.is_empty() {
+                        println!("  No vacations planned");
+                    }
+                    for v in &views {
+                        println!(
+                            "🏖️  {} to {} ({} habits){}",
+                            v.from,
+                            v.until.map_or("-".into(), |u| u.to_string()),
+                            v.habits,
+                            if v.from <= today && v.until.is_none_or(|u| u >= today) {
+                                "  ← now"
+                            } else {
+                                ""
+                            }
+                        );
+                    }
+                })
+            }
+            VacationCommand::Remove { from } => {
+                let from = parse_day(&from, today)?;
+                let Some(vacation) = store.vacations().into_iter().find(|v| v.from == from) else {
+                    return Err(HabitError::NotFound(format!("vacation starting {}", from)));
+                };
+                let view = VacationView::new(&store, vacation);
+                for habit in &mut store.habits {
+                    habit.pauses.retain(|p| *p != vacation);
+                }
+                repo.save(&store)?;
+                out.emit(&view, || {
+                    println!(
+                        "🗑️  Removed the vacation from {} ({} habits)",
+                        view.from, view.habits
+                    )
+                })
+            }
+        },
+        Commands::Storage {
+            action: StorageCommand::Migrate { to, path, force },
+        } => {
+            let path = path
+                .or_else(|| repo.location().map(|p| p.with_extension(to.extension())))
+                .ok_or_else(|| HabitError::BackendUnavailable("no target --path given".into()))?;
+            if repo.location() == Some(path.as_path()) {
+                return Err(HabitError::AlreadyExists(path.display().to_string()));
+            }
+            let target = StorageLocation::new(to, path);
+            let mut dest = target.open()?;
+            let _dest_lock = dest.lock()?;
+            if !force && !dest.load()?.habits.is_empty() {
+                return Err(HabitError::AlreadyExists(format!(
+                    "{} (use --force to overwrite)",
+                    target
+                )));
+            }
+            dest.save(&store)?;
+            if dest.load()? != store {
+                return Err(HabitError::MigrationFailed(target.to_string()));
+            }
+            let view = MigrationView {
+                target: target.to_string(),
+                habits: store.habits.len(),
+                completions: store.habits.iter().map(|h| h.completions.len()).sum(),
+            };
+            out.emit(&view, || {
+                println!(
+                    "📦 Migrated {} habits and {} completions to {}",
+                    view.habits, view.completions, target
+                );
+                println!(
+                    "  Set HABIT_STORAGE={} (or the 'storage' config key) to use it",
+                    target
+                );
+            })
+        }
+        Commands::Backup { action } => {
+            let backups = repo.location().map(Backups::for_store).ok_or_else(|| {
+                HabitError::BackendUnavailable("backups need a file-backed store".into())
+            })?;
+            let view = |number: usize, entry: &BackupEntry| BackupView {
+                number,
+                id: entry.id(),
+                taken_at: entry.taken_at,
+                path: entry.path.display().to_string(),
+                habits: entry.load().ok().map(|s| s.habits.len()),
+            };
+            match action {
+                BackupCommand::List => {
+                    let views: Vec<BackupView> = backups
+                        .list()?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, entry)| view(i + 1, entry))
+                        .collect();
+                    out.rows(&views, || {
+                        if views.is_empty() {
+                            println!("No backups in {}", backups.dir().display());
+                            return;
+                        }
+                        println!("🗄️  Backups in {}:", backups.dir().display());
+                        for v in &views {
+                            println!(
+                                "{:>3}. {}  {}  ({})",
+                                v.number,
+                                v.id,
+                                zone.format(v.taken_at, "%Y-%m-%d %H:%M:%S"),
+                                v.habits
+                                    .map_or("unreadable".into(), |n| format!("{} habits", n))
+                            );
+                        }
+                    })
+                }
+                BackupCommand::Create => {
+                    let entry = backups.create(&store)?;
+                    backups.prune(&Config::load()?.backups)?;
+                    let view = view(1, &entry);
+                    out.emit(&view, || {
+                        println!("🗄️  Backed up {} habits as {}", store.habits.len(), view.id)
+                    })
+                }
+                BackupCommand::Restore { id, yes } => {
+                    let entry = backups.find(&id)?;
+                    let restored = entry.load()?;
+                    let changes = diff::diff(&store, &restored);
+                    let settings_changed = restored.settings != store.settings;
+                    let unchanged = changes.is_empty() && !settings_changed;
+                    let view = RestoreView {
+                        backup: entry.id(),
+                        applied: yes && !unchanged,
+                        settings_changed,
+                        changes: changes.iter().map(ChangeView::from).collect(),
+                    };
+                    if view.applied {
+                        // saving backs up the store being replaced, so a restore can itself be undone
+                        repo.save(&restored)?;
+                    }
+                    out.emit(&view, || {
+                        if unchanged {
+                            println!("Backup {} matches the current store", view.backup);
+                            return;
+                        }
+                        println!("Restoring {} would:", view.backup);
+                        for change in &view.changes {
+                            println!("  {}", change.summary);
+                        }
+                        if settings_changed {
+                            println!("  ~ settings changed");
+                        }
+                        if view.applied {
+                            println!("♻️  Restored backup {}", view.backup);
+                        } else {
+                            println!("Run again with --yes to restore.");
+                        }
+                    })
+                }
+            }
+        }
+        Commands::Export { path, completions } => {
+            let calendar = cli.format == Format::Ics
+                || (cli.format == Format::Text
+                    && path
+                        .as_deref()
+                        .and_then(Path::extension)
+                        .is_some_and(|e| e.eq_ignore_ascii_case("ics")));
+            let delimiter = match (cli.format, &path) {
+                (Format::Json, _) => None,
+                (Format::Tsv, _) => Some(b'\t'),
+                (_, Some(path)) => Some(csv::delimiter(path)),
+                (_, None) => Some(b','),
+            };
+            match &completions {
+                _ if calendar => {
+                    if completions.is_some() {
+                        return Err(HabitError::UnsupportedFormat(
+                            "ics with --completions".into(),
+                        ));
+                    }
+                    write_export(path.as_deref(), |writer| {
+                        ics::write(writer, &store, &zone, Utc::now())
+                    })?;
+                }
+                Some(file) => {
+                    let habits = csv::habit_rows(&store);
+                    export(path.as_deref(), delimiter, &FIELDS[..9], &habits)?;
+                    let rows = csv::completion_rows(&store, &zone);
+                    export(Some(file), delimiter, &COMPLETION_FIELDS, &rows)?;
+                }
+                None => export(
+                    path.as_deref(),
+                    delimiter,
+                    &FIELDS,
+                    &csv::export_rows(&store, &zone),
+                )?,
+            }
+            // the data itself went to stdout
+            let Some(path) = path else {
+                return Ok(());
+            };
+            let view = ExportView {
+                files: [Some(&path), completions.as_ref()]
+                    .iter()
+                    .flatten()
+                    .map(|p| p.display().to_string())
+                    .collect(),
+                // the calendar only has events for active habits
+                habits: store
+                    .habits
+                    .iter()
+                    .filter(|h| h.is_active || !calendar)
+                    .count(),
+                completions: store.habits.iter().map(|h| h.completions.len()).sum(),
+            };
+            out.emit(&view, || {
+                println!(
+                    "📤 Exported {} habits and {} completions to {}",
+                    view.habits,
+                    view.completions,
+                    view.files.join(" and ")
+                );
+                if calendar && let Ok(full) = std::fs::canonicalize(&path) {
+                    println!(
+                        "📅 Subscribe to file://{} in your calendar app; export again to refresh it",
+                        full.display()
+                    );
+                }
+            })
+        }
+        Commands::Import {
+            files,
+            from,
+            mappings,
+            merge_by,
+            dry_run,
+        } => {
+            let (mut records, mut unmapped) = (Vec::new(), Vec::new());
+            for file in &files {
+                let batch = import::read(from, file, &mappings)?;
+                records.extend(batch.records);
+                unmapped.extend(batch.unmapped);
+            }
+            let summary = import::merge(&mut store, &records, merge_by, &zone)?;
+            if !dry_run && (!summary.created.is_empty() || summary.added > 0) {
+                repo.save(&store)?;
+            }
+            let view = ImportView {
+                files: files.iter().map(|f| f.display().to_string()).collect(),
+                dry_run,
+                summary,
+                unmapped,
+            };
+            out.emit(&view, || {
+                if dry_run {
+                    println!("Importing {} would:", view.files.join(", "));
+                } else {
+                    println!("📥 Imported {}:", view.files.join(", "));
+                }
+                for name in &view.summary.created {
+                    println!("  + new habit '{}'", name);
+                }
+                for name in &view.summary.matched {
+                    println!("  = existing habit '{}'", name);
+                }
+                println!(
+                    "  {} completions added, {} skipped as already logged",
+                    view.summary.added, view.summary.duplicates
+                );
+                if !view.unmapped.is_empty() {
+                    println!("⚠️  Not carried over exactly:");
+                    for line in &view.unmapped {
+                        println!("  {}", line);
+                    }
+                }
+                if dry_run {
+                    println!("Run again without --dry-run to save.");
+                }
+            })
+        }
+        Commands::Undo { count } => {
+            let journal = journal.ok_or_else(no_journal)?;
+            let entries = journal.entries()?;
+            let history = History::of(&entries);
+            // apply everything before saving so a conflict leaves the store untouched
+            let mut undone = Vec::new();
+            for seq in history.done.iter().rev().take(count) {
+                let Some(entry) = entries.iter().find(|e| e.seq == *seq) else {
+                    continue;
+                };
+                let events: Vec<Event> = entry.events.iter().rev().map(Event::inverse).collect();
+                for event in &events {
+                    event.apply(&mut store)?;
+                }
+                undone.push((entry, events));
+            }
+            if !undone.is_empty() {
+                repo.save(&store)?;
+            }
+            let mut views = Vec::new();
+            for (entry, events) in &undone {
+                let appended = journal.append(
+                    &format!("undo #{}", entry.seq),
+                    EntryKind::Undo { of: entry.seq },
+                    events.clone(),
+                )?;
+                views.push(JournalEntryView::new(&appended, false));
+            }
+            out.rows(&views, || {
+                if undone.is_empty() {
+                    println!("Nothing to undo");
+                }
+                for (entry, _) in &undone {
+                    println!("↩️  Undid #{}: {}", entry.seq, entry.command);
+                }
+            })
+        }
+        Commands::Redo { count } => {
+            let journal = journal.ok_or_else(no_journal)?;
+            let entries = journal.entries()?;
+            let history = History::of(&entries);
+            let mut redone = Vec::new();
+            for seq in history.undone.iter().rev().take(count) {
+                let Some(entry) = entries.iter().find(|e| e.seq == *seq) else {
+                    continue;
+                };
+                for event in &entry.events {
+                    event.apply(&mut store)?;
+                }
+                redone.push(entry);
+            }
+            if !redone.is_empty() {
+                repo.save(&store)?;
+            }
+            let mut views = Vec::new();
+            for entry in &redone {
+                let appended = journal.append(
+                    &format!("redo #{}", entry.seq),
+                    EntryKind::Redo { of: entry.seq },
+                    entry.events.clone(),
+                )?;
+                views.push(JournalEntryView::new(&appended, false));
+            }
+            out.rows(&views, || {
+                if redone.is_empty() {
+                    println!("Nothing to redo");
+                }
+                for entry in &redone {
+                    println!("↪️  Redid #{}: {}", entry.seq, entry.command);
+                }
+            })
+        }
+        Commands::Log { limit, verify } => {
+            let journal = journal.ok_or_else(no_journal)?;
+            let entries = journal.entries()?;
+            if verify {
+                let rebuilt = journal.replay()?;
+                let view = VerifyView {
+                    entries: entries.len(),
+                    consistent: rebuilt == store,
+                    settings_differ: rebuilt.settings != store.settings,
+                    differences: diff::diff(&rebuilt, &store)
+                        .iter()
+                        .map(ChangeView::from)
+                        .collect(),
+                };
+                return out.emit(&view, || {
+                    if entries.is_empty() {
+                        println!("No changes recorded yet");
+                    } else if view.consistent {
+                        println!(
+                            "✅ Replaying {} journal entries rebuilds the store",
+                            view.entries
+                        );
+                    } else {
+                        println!("⚠️  Replaying the journal does not rebuild the store:");
+                        for change in &view.differences {
+                            println!("  {}", change.summary);
+                        }
+                        if view.settings_differ {
+                            println!("  ~ settings differ");
+                        }
+                    }
+                });
+            }
+            let history = History::of(&entries);
+            let views: Vec<JournalEntryView> = entries
+                .iter()
+                .rev()
+                .take(limit)
+                .map(|e| JournalEntryView::new(e, history.undone.contains(&e.seq)))
+                .collect();
+            out.rows(&views, || {
+                if views.is_empty() {
+                    println!("No changes recorded yet");
+                }
+                for view in &views {
+                    println!(
+                        "#{:<4} {}  {}{}",
+                        view.seq,
+                        zone.format(view.at, "%Y-%m-%d %H:%M"),
+                        view.command,
+                        if view.undone { "  (undone)" } else { "" }
+                    );
+                    for event in view.events.iter().take(5) {
+                        println!("       {}", event);
+                    }
+                    if view.events.len() > 5 {
+                        println!("       … and {} more", view.events.len() - 5);
+                    }
+                }
+            })
+        }
+        Commands::Doctor { fix } => {
+            let issues = store.validate(&zone);
+            let repairable = issues.iter().filter(|i| i.kind.repair().is_some()).count();
+            let fixed = fix && repairable > 0;
+            if fixed {
+                store.repair(&zone);
+                repo.save(&store)?;
+            }
+            let views: Vec<IssueView> = issues
+                .iter()
+                .map(|i| IssueView::new(i, fixed && i.kind.repair().is_some()))
+                .collect();
+            out.rows(&views, || {
+                if issues.is_empty() {
+                    println!("✅ No problems found in {} habits", store.habits.len());
+                    return;
+                }
+                let errors = issues
+                    .iter()
+                    .filter(|i| i.severity() == Severity::Error)
+                    .count();
+                println!(
+                    "🩺 Checked {} habits: {} errors, {} warnings",
+                    store.habits.len(),
+                    errors,
+                    issues.len() - errors
+                );
+                for issue in &issues {
+                    let icon = match issue.severity() {
+                        Severity::Error => "❌",
+                        Severity::Warning => "⚠️ ",
+                    };
+                    println!("  {} {}", icon, issue.message);
+                    if fix {
+                        match issue.kind.repair() {
+                            Some(repair) => println!("     fix: {}", repair),
+                            None => println!("     kept: --fix leaves these alone"),
+                        }
+                    }
+                }
+                if fixed {
+                    println!("🔧 Fixed {} issues", repairable);
+                } else if repairable > 0 {
+                    println!("Run `habit doctor --fix` to repair them.");
+                }
+            })
+        }
+        Commands::Recover { .. } => unreachable!("handled before the store is loaded"),
+        #[cfg(feature = "tui")]
+        Commands::Tui => unreachable!("handled before the store is loaded"),
+        Commands::Config { timezone } => {
+            let updated = timezone.is_some();
+            if let Some(tz) = timezone {
+                store.settings.timezone = if tz.eq_ignore_ascii_case("null") {
+                    None
+                } else {
+                    Some(tz.parse()?)
+                };
+                repo.save(&store)?;
+            }
+            let effective = Zone::resolve(cli.tz.as_deref(), store.settings.timezone)?;
+            let view = ConfigView {
+                timezone: store.settings.timezone.map(|tz| tz.to_string()),
+                effective_timezone: effective.to_string(),
+                today: effective.today(),
+                updated,
+            };
+            out.emit(&view, || {
+                if updated {
+                    println!("⚙️  Settings updated");
+                }
+                match &view.timezone {
+                    Some(tz) => println!("  Timezone: {}", tz),
+                    None => println!("  Timezone: not set (UTC)"),
+                }
+                println!(
+                    "  In effect: {} (today is {})",
+                    view.effective_timezone, view.today
+                );
+            })
+        }
+    }
+}
+
+fn recover(
+    repo: &mut dyn HabitRepository,
+    source: Option<RecoverySource>,
+    yes: bool,
+    out: Output,
+) -> Result<()> {
+    let path = repo
+        .location()
+        .ok_or_else(|| HabitError::BackendUnavailable("recovery needs a file-backed store".into()))?
+        .to_path_buf();
+    match repo.load() {
+        Ok(store) if source.is_none() => {
+            let view = RecoveryView {
+                source: None,
+                detail: "the store loads fine".into(),
+                habits: store.habits.len(),
+                candidates: Vec::new(),
+                lost: Vec::new(),
+                applied: false,
+            };
+            return out.emit(&view, || {
+                println!("✅ {} loads fine; nothing to recover", path.display())
+            });
+        }
+        Ok(_) => {}
+        Err(err) => out.note(format!("⚠️  {}", err)),
+    }
+    let salvageable = repo.backend() == Some(Backend::Json);
+    if source == Some(RecoverySource::Salvage) && !salvageable {
+        return Err(HabitError::BackendUnavailable(
+            "salvage only reads JSON stores; recover from the journal or a backup".into(),
+        ));
+    }
+    let (candidates, salvaged) = recovery::candidates(&path, salvageable);
+    let options: Vec<CandidateView> = candidates
+        .iter()
+        .map(|c| CandidateView {
+            source: c.source.to_string(),
+            detail: c.detail.clone(),
+            habits: c.store.habits.len(),
+        })
+        .collect();
+    let chosen = match source {
+        Some(source) => candidates
+            .into_iter()
+            .find(|c| c.source == source)
+            .ok_or_else(|| {
+                HabitError::BackupNotFound(format!("no usable {} to recover from", source))
+            })?,
+        None => candidates
+            .into_iter()
+            .next()
+            .ok_or_else(|| HabitError::BackupNotFound("nothing to recover from".into()))?,
+    };
+    // what the damaged file still shows that the recovered store lacks
+    let mut lost: Vec<String> = diff::diff(&chosen.store, &salvaged.store)
+        .iter()
+        .filter(|change| !matches!(change, diff::HabitChange::Removed(_)))
+        .map(|change| change.describe())
+        .collect();
+    if chosen.source == RecoverySource::Salvage {
+        lost = salvaged.lost.clone();
+    } else if !salvaged.lost.is_empty() {
+        lost.push("anything else in the unreadable part of the damaged file".into());
+    }
+    if yes {
+        // saving keeps the damaged file as a backup before replacing it
+        repo.save(&chosen.store)?;
+        let journal = Journal::for_store(&path);
+        if chosen.source != RecoverySource::Journal
+            && let Ok(replayed) = journal.replay()
+        {
+            journal.record("recover", &replayed, &chosen.store)?;
+        }
+    }
+    let view = RecoveryView {
+        source: Some(chosen.source.to_string()),
+        detail: chosen.detail,
+        habits: chosen.store.habits.len(),
+        candidates: options,
+        lost,
+        applied: yes,
+    };
+    out.emit(&view, || {
+        println!("Recovery options:");
+        for option in &view.candidates {
+            println!(
+                "  {:<8} {} ({} habits)",
+                option.source, option.detail, option.habits
+            );
+        }
+        println!(
+            "Recovering from {}: {}",
+            view.source.as_deref().unwrap_or_default(),
+            view.detail
+        );
+        if view.lost.is_empty() {
+            println!("  Nothing known to be lost");
+        } else {
+            println!("  Lost:");
+            for item in &view.lost {
+                println!("    {}", item);
+            }
+        }
+        if view.applied {
+            println!("♻️  Recovered {} habits", view.habits);
+        } else {
+            println!("Run again with --yes to replace the store.");
+        }
+    })
+}
+
+fn percent(rate: Option<f64>) -> String {
+    rate.map_or("-".into(), |r| format!("{:.0}%", r * 100.0))
+}
+
+fn print_stats_table(views: &[StatsView]) {
+    if views.is_empty() {
+        println!("  No habits to display");
+        return;
+    }
+    let headers = ["NAME", "7D", "30D", "90D", "365D", "TREND", "BEST DAY"];
+    let rows: Vec<Vec<String>> = views
+        .iter()
+        .map(|v| {
+            let s = &v.stats;
+            vec![
+                v.name.clone(),
+                percent(s.rates.last_7_days),
+                percent(s.rates.last_30_days),
+                percent(s.rates.last_90_days),
+                percent(s.rates.last_365_days),
+                s.trend.map_or("-".into(), |t| match t.direction {
+                    Direction::Improving => "improving".into(),
+                    Direction::Steady => "steady".into(),
+                    Direction::Declining => "declining".into(),
+                }),
+                s.best_weekday.map_or("-".into(), |w| w.weekday.to_string()),
+            ]
+        })
+        .collect();
+    println!("{}", table::render(&headers, &rows));
+}
+
+fn print_stats(view: &StatsView) {
+    let s = &view.stats;
+    println!("📊 {} ({})", view.name, view.schedule);
+    println!(
+        "  Completion   7d {}  30d {}  90d {}  365d {}",
+        percent(s.rates.last_7_days),
+        percent(s.rates.last_30_days),
+        percent(s.rates.last_90_days),
+        percent(s.rates.last_365_days)
+    );
+    if let Some(t) = s.trend {
+        let arrow = match t.direction {
+            Direction::Improving => "📈 improving",
+            Direction::Steady => "➡️  steady",
+            Direction::Declining => "📉 declining",
+        };
+        println!(
+            "  Trend        {} ({} vs {} the 30 days before)",
+            arrow,
+            percent(Some(t.current)),
+            percent(Some(t.previous))
+        );
+    }
+    if let (Some(best), Some(worst)) = (s.best_weekday, s.worst_weekday) {
+        println!(
+            "  Best day     {} ({})",
+            best.weekday,
+            percent(Some(best.rate))
+        );
+        println!(
+            "  Worst day    {} ({})",
+            worst.weekday,
+            percent(Some(worst.rate))
+        );
+    }
+    if let Some(time) = s.average_time {
+        println!("  Usual time   {}", time.format("%H:%M"));
+    }
+    if let Some(gap) = s.longest_gap {
+        println!(
+            "  Longest gap  {} days ({} → {})",
+            gap.days, gap.from, gap.to
+        );
+    }
+    if let Some(t) = &s.totals {
+        println!(
+            "  Total        {} {} ({} in the last 30 days)",
+            t.total, t.unit, t.last_30_days
+        );
+        println!("  Average      {:.1} {} per day logged", t.average, t.unit);
+        if let Some(day) = t.best_day {
+            println!("  Best total   {} {} on {}", t.best_amount, t.unit, day);
+        }
+    }
+}
+
+fn print_today(views: &[HabitView], today: NaiveDate) {
+    println!("📅 {}", today.format("%A %Y-%m-%d"));
+    if views.is_empty() {
+        println!("  🎉 Nothing due today");
+        return;
+    }
+    let columns = [
+        Column::Name,
+        Column::Today,
+        Column::Progress,
+        Column::Streak,
+    ];
+    let mut headers = vec![""];
+    headers.extend(columns.iter().map(|c| c.header()));
+    let rows: Vec<Vec<String>> = views
+        .iter()
+        .map(|v| {
+            let mut row = vec![if v.done_today { "[x]" } else { "[ ]" }.to_string()];
+            row.extend(columns.iter().map(|c| c.cell(v)));
+            row
+        })
+        .collect();
+    println!("{}", table::render(&headers, &rows));
+    // paused habits are listed with --all but not expected
+    let expected = views.iter().filter(|v| v.done_today || !v.paused).count();
+    let done = views.iter().filter(|v| v.done_today).count();
+    if expected == 0 {
+        println!("⏸️  Everything here is paused today");
+    } else if done == expected {
+        println!("🎉 All {} done for today", done);
+    } else {
+        println!("{} of {} done, {} to go", done, expected, expected - done);
+    }
+}
+
+fn print_long(h: &HabitView) {
+    println!("ID: {} | {}", h.id, h.name);
+    if let Some(desc) = &h.description {
+        println!("  Description: {}", desc);
+    }
+    if !h.tags.is_empty() {
+        println!("  Tags: {}", h.tags.join(", "));
+    }
+    println!("  Created: {}", h.created);
+    println!(
+        "  Schedule: {}{}",
+        h.schedule,
+        if h.due_today { " (due today)" } else { "" }
+    );
+    println!(
+        "  This {}: {}/{} completed",
+        h.progress.period.label(1),
+        h.progress.done,
+        h.progress.target
+    );
+    if let (Some(target), Some(unit)) = (h.target, &h.unit) {
+        println!("  Today: {}/{} {}", h.today_total, target, unit);
+    }
+    println!("  Completions: {} days", h.completed_days);
+    for p in &h.pauses {
+        let kind = if p.vacation { "Vacation" } else { "Paused" };
+        match p.until {
+            Some(until) => println!("  {}: {} to {}", kind, p.from, until),
+            None => println!("  {}: from {} until resumed", kind, p.from),
+        }
+    }
+    if let Some(rate) = h.rate {
+        println!("  Completion rate: {:.0}%", rate * 100.0);
+    }
+    println!(
+        "  Streak: {} {} (longest {}){}",
+        h.streak.current,
+        h.streak.unit.label(h.streak.current),
+        h.streak.longest,
+        if h.streak.at_risk {
+            " ⚠️  at risk today"
+        } else {
+            ""
+        }
+    );
+    println!("  Active: {}", h.active);
+}
+
+/// Writes `rows` to `path` or stdout, as delimited text or a JSON array.
+fn export<T: Serialize>(
+    path: Option<&Path>,
+    delimiter: Option<u8>,
+    header: &[&str],
+    rows: &[T],
+) -> Result<()> {
+    write_export(path, |writer| match delimiter {
+        Some(delimiter) => csv::write(writer, delimiter, header, rows),
+        None => {
+            serde_json::to_writer_pretty(&mut *writer, rows)?;
+            writeln!(writer)?;
             Ok(())
         }
+    })
+}
+
+/// Writes to stdout, or replaces `path` in one step so a calendar or
+/// spreadsheet reading it never sees half a file.
+fn write_export(
+    path: Option<&Path>,
+    write: impl FnOnce(&mut dyn Write) -> Result<()>,
+) -> Result<()> {
+    let Some(path) = path else {
+        return write(&mut std::io::stdout().lock());
+    };
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    if let Err(err) = write(&mut File::create(&tmp)?) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(err);
     }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn no_journal() -> HabitError {
+    HabitError::BackendUnavailable("the journal needs a file-backed store".into())
 }
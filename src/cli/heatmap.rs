@@ -0,0 +1,137 @@
+use crate::models::habit::Habit;
+use crate::models::schedule::PeriodKind;
+use crate::models::timezone::Zone;
+use chrono::{Datelike, Days, NaiveDate};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+const ROWS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+/// How heatmap cells are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Green shades on a terminal that takes ANSI colors.
+    Color,
+    /// Shaded blocks, for UTF-8 output without color.
+    Unicode,
+    Ascii,
+}
+
+impl Style {
+    /// Color on a terminal unless `NO_COLOR` is set, shaded blocks when the
+    /// locale is UTF-8, plain ASCII otherwise.
+    pub fn detect() -> Self {
+        let var = |key| std::env::var(key).unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .map(var)
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+            .to_uppercase();
+        if var("TERM") == "dumb" || !(locale.contains("UTF-8") || locale.contains("UTF8")) {
+            Style::Ascii
+        } else if std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() {
+            Style::Color
+        } else {
+            Style::Unicode
+        }
+    }
+
+    fn cell(self, level: u8) -> String {
+        match self {
+            Style::Color => {
+                let color = [238, 22, 28, 34, 40][level as usize];
+                format!("\x1b[38;5;{}m■\x1b[0m", color)
+            }
+            Style::Unicode => ["·", "░", "▒", "▓", "█"][level as usize].into(),
+            Style::Ascii => [".", "-", "+", "*", "#"][level as usize].into(),
+        }
+    }
+}
+
+/// Share of what was expected that got done on each day from `from` to `to`,
+/// averaged over `habits`. A habit counts on days it was logged or still
+/// due; `None` marks days nothing was expected, including days before the
+/// habits existed, after today, and today until something is logged.
+pub fn day_ratios(
+    habits: &[&Habit],
+    from: NaiveDate,
+    to: NaiveDate,
+    today: NaiveDate,
+    zone: &Zone,
+) -> BTreeMap<NaiveDate, Option<f64>> {
+    from.iter_days()
+        .take_while(|d| *d <= to)
+        .map(|day| {
+            let ratios: Vec<f64> = habits
+                .iter()
+                .filter(|h| day <= today && day >= h.created_day(zone))
+                .filter_map(|h| {
+                    let ratio = h.day_ratio(day, zone);
+                    let expected = ratio > 0.0 || (day < today && h.is_due(day, zone));
+                    expected.then_some(ratio)
+                })
+                .collect();
+            let mean =
+                (!ratios.is_empty()).then(|| ratios.iter().sum::<f64>() / ratios.len() as f64);
+            (day, mean)
+        })
+        .collect()
+}
+
+/// Shade from 0 (nothing done) to 4 (everything done).
+pub fn level(ratio: f64) -> u8 {
+    if ratio <= 0.0 {
+        0
+    } else {
+        (ratio * 4.0).ceil().clamp(1.0, 4.0) as u8
+    }
+}
+
+/// Draws `ratios` with weeks as columns and weekdays as rows, month names
+/// above and a legend below.
+pub fn render(ratios: &BTreeMap<NaiveDate, Option<f64>>, style: Style) -> String {
+    let (Some(from), Some(to)) = (ratios.keys().next(), ratios.keys().next_back()) else {
+        return String::new();
+    };
+    let first = PeriodKind::Week.start_of(*from);
+    let weeks = ((*to - first).num_days() / 7 + 1) as usize;
+
+    let mut months = vec![' '; weeks + 3];
+    let mut free = 0;
+    for week in 0..weeks {
+        let start = first + Days::new(week as u64 * 7);
+        let label = start
+            .iter_days()
+            .take(7)
+            .find(|d| ratios.contains_key(d) && (d.day() == 1 || d == from));
+        if let Some(day) = label
+            && week >= free
+        {
+            for (i, c) in day.format("%b").to_string().chars().enumerate() {
+                months[week + i] = c;
+            }
+            free = week + 4;
+        }
+    }
+    let mut lines = vec![format!("    {}", String::from_iter(months).trim_end())];
+
+    for (row, label) in ROWS.iter().enumerate() {
+        let mut line = format!("{:<4}", label);
+        for week in 0..weeks {
+            let day = first + Days::new((week * 7 + row) as u64);
+            match ratios.get(&day) {
+                Some(Some(ratio)) => line.push_str(&style.cell(level(*ratio))),
+                _ => line.push(' '),
+            }
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    let scale: Vec<String> = (0..=4).map(|l| style.cell(l)).collect();
+    lines.push(format!("    Less {} More", scale.join(" ")));
+    let expected = ratios.values().flatten().count();
+    let full = ratios.values().flatten().filter(|r| **r >= 1.0).count();
+    lines.push(format!("    {} of {} expected days done", full, expected));
+    lines.join("\n")
+}
@@ -0,0 +1,194 @@
+use crate::error::{HabitError, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// How command results are printed. Everything but `text` is meant for
+/// scripts and stays stable; the shapes are described in `docs/output.md`.
+/// `ics` is only for `export`; other commands refuse it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Csv,
+    Tsv,
+    Ics,
+}
+
+impl FromStr for Format {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "ics" => Ok(Format::Ics),
+            _ => Err(HabitError::UnsupportedFormat(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Text => write!(f, "text"),
+            Format::Json => write!(f, "json"),
+            Format::Csv => write!(f, "csv"),
+            Format::Tsv => write!(f, "tsv"),
+            Format::Ics => write!(f, "ics"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Output {
+    pub format: Format,
+}
+
+impl Output {
+    pub fn new(format: Format) -> Self {
+        Self { format }
+    }
+
+    /// Whether messages are for people. An `ics` export is a file, and
+    /// what gets printed around it is text.
+    pub fn is_text(&self) -> bool {
+        matches!(self.format, Format::Text | Format::Ics)
+    }
+
+    /// Prints a single result: `text` renders it for people, the other
+    /// formats serialize `data` (as one CSV/TSV row).
+    pub fn emit<T: Serialize>(&self, data: &T, text: impl FnOnce()) -> Result<()> {
+        match self.format {
+            Format::Text | Format::Ics => text(),
+            Format::Json => println!("{}", serde_json::to_string_pretty(data)?),
+            Format::Csv | Format::Tsv => self.table(&[serde_json::to_value(data)?]),
+        }
+        Ok(())
+    }
+
+    /// Prints a list-like result, one CSV/TSV line per row.
+    pub fn rows<T: Serialize>(&self, rows: &[T], text: impl FnOnce()) -> Result<()> {
+        match self.format {
+            Format::Text | Format::Ics => text(),
+            Format::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+            Format::Csv | Format::Tsv => {
+                let rows = rows
+                    .iter()
+                    .map(serde_json::to_value)
+                    .collect::<serde_json::Result<Vec<_>>>()?;
+                self.table(&rows);
+            }
+        }
+        Ok(())
+    }
+
+    /// Incidental messages: part of the text output, but kept off stdout in
+    /// the machine formats.
+    pub fn note(&self, message: impl fmt::Display) {
+        if self.is_text() {
+            println!("{}", message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Reports a failed command, as JSON on stderr for the machine formats.
+    pub fn error(&self, err: &HabitError) {
+        if self.is_text() {
+            eprintln!("❌ Error: {}", err);
+            return;
+        }
+        let body = serde_json::json!({
+            "error": { "code": err.code(), "message": err.to_string() }
+        });
+        eprintln!("{}", body);
+    }
+
+    fn table(&self, rows: &[Value]) {
+        let sep = if self.format == Format::Tsv {
+            '\t'
+        } else {
+            ','
+        };
+        let rows: Vec<Map<String, Value>> = rows
+            .iter()
+            .map(|row| {
+                let mut flat = Map::new();
+                flatten("", row, &mut flat);
+                flat
+            })
+            .collect();
+        // rows may differ in optional columns, so take every key in first-seen order
+        let mut headers: Vec<&String> = Vec::new();
+        for key in rows.iter().flat_map(|row| row.keys()) {
+            if !headers.contains(&key) {
+                headers.push(key);
+            }
+        }
+        if headers.is_empty() {
+            return;
+        }
+        let line = |cells: Vec<String>| {
+            cells
+                .iter()
+                .map(|c| self.escape(c))
+                .collect::<Vec<_>>()
+                .join(&sep.to_string())
+        };
+        println!("{}", line(headers.iter().map(|h| h.to_string()).collect()));
+        for row in &rows {
+            println!(
+                "{}",
+                line(headers.iter().map(|h| cell(row.get(*h))).collect())
+            );
+        }
+    }
+
+    fn escape(&self, cell: &str) -> String {
+        match self.format {
+            Format::Tsv => cell.replace(['\t', '\n', '\r'], " "),
+            _ if cell.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            }
+            _ => cell.to_string(),
+        }
+    }
+}
+
+/// Nested objects become dotted columns (`streak.current`).
+fn flatten(prefix: &str, value: &Value, out: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Scalars print bare, lists of scalars join with `;`, anything deeper stays JSON.
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(items)) if items.iter().all(|i| !i.is_object() && !i.is_array()) => items
+            .iter()
+            .map(|i| cell(Some(i)))
+            .collect::<Vec<_>>()
+            .join(";"),
+        Some(other) => other.to_string(),
+    }
+}
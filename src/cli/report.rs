@@ -0,0 +1,248 @@
+use crate::cli::table;
+use crate::cli::views::ReportRow;
+use crate::error::{HabitError, Result};
+use crate::models::habit::Habit;
+use crate::models::schedule::PeriodKind;
+use crate::models::timezone::Zone;
+use chrono::{Datelike, Days, Months, NaiveDate};
+
+/// The ISO week or calendar month a report covers, by its first day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Week(NaiveDate),
+    Month(NaiveDate),
+}
+
+impl Period {
+    /// Parses `2026-W41` or `last`; the current week when `None`.
+    pub fn week(input: Option<&str>, today: NaiveDate) -> Result<Self> {
+        let this = PeriodKind::Week.start_of(today);
+        let invalid = || HabitError::InvalidPeriod(input.unwrap_or_default().to_string());
+        let start = match input.map(|s| s.trim().to_lowercase()) {
+            None => this,
+            Some(s) if s == "last" => this - Days::new(7),
+            Some(s) => {
+                let (year, week) = s.split_once("-w").ok_or_else(invalid)?;
+                let year = year.parse().map_err(|_| invalid())?;
+                let week = week.parse().map_err(|_| invalid())?;
+                NaiveDate::from_isoywd_opt(year, week, chrono::Weekday::Mon).ok_or_else(invalid)?
+            }
+        };
+        Ok(Period::Week(start))
+    }
+
+    /// Parses `2026-10` or `last`; the current month when `None`.
+    pub fn month(input: Option<&str>, today: NaiveDate) -> Result<Self> {
+        let this = PeriodKind::Month.start_of(today);
+        let invalid = || HabitError::InvalidPeriod(input.unwrap_or_default().to_string());
+        let start = match input.map(|s| s.trim().to_lowercase()) {
+            None => this,
+            Some(s) if s == "last" => this - Months::new(1),
+            Some(s) => NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d")
+                .map_err(|_| invalid())?,
+        };
+        Ok(Period::Month(start))
+    }
+
+    pub fn start(self) -> NaiveDate {
+        match self {
+            Period::Week(start) | Period::Month(start) => start,
+        }
+    }
+
+    pub fn end(self) -> NaiveDate {
+        match self {
+            Period::Week(start) => PeriodKind::Week.end_of(start),
+            Period::Month(start) => PeriodKind::Month.end_of(start),
+        }
+    }
+
+    /// `2026-W41` or `2026-10`.
+    pub fn label(self) -> String {
+        match self {
+            Period::Week(start) => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Month(start) => start.format("%Y-%m").to_string(),
+        }
+    }
+
+    fn title(self) -> String {
+        match self {
+            Period::Week(start) => {
+                let week = start.iso_week();
+                format!("Week {}, {}", week.week(), week.year())
+            }
+            Period::Month(start) => start.format("%B %Y").to_string(),
+        }
+    }
+}
+
+/// One row per habit that was active by the end of the period, or that was
+/// logged during it. A period still in progress counts up to today.
+pub fn rows(habits: &[Habit], period: Period, today: NaiveDate, zone: &Zone) -> Vec<ReportRow> {
+    let (start, end) = (period.start(), period.end());
+    let last = end.min(today);
+    habits
+        .iter()
+        .filter(|h| h.created_day(zone) <= end)
+        .filter(|h| {
+            h.is_active
+                || h.completions
+                    .iter()
+                    .any(|c| (start..=end).contains(&zone.date_of(c.at)))
+        })
+        .map(|h| {
+            let (completed, scheduled) = if start <= today {
+                h.schedule_counts_within(start, end, today, zone)
+            } else {
+                (0, 0)
+            };
+            let before = h.streaks(start - Days::new(1), zone);
+            let after = h.streaks(last, zone);
+            ReportRow {
+                period: period.label(),
+                from: start,
+                to: end,
+                id: h.id,
+                name: h.name.clone(),
+                schedule: h.schedule.to_string(),
+                scheduled,
+                completed,
+                hit_rate: (scheduled > 0).then(|| f64::from(completed) / f64::from(scheduled)),
+                streak_unit: after.unit,
+                streak_before: before.current,
+                streak_after: after.current,
+                amount: h.goal.as_ref().map(|_| {
+                    h.day_totals(zone)
+                        .range(start..=end)
+                        .fold(0.0, |sum, (_, amount)| sum + amount)
+                }),
+                unit: h.goal.as_ref().map(|g| g.unit.clone()),
+            }
+        })
+        .collect()
+}
+
+/// How the text report is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Text,
+    Markdown,
+    Html,
+}
+
+const HEADERS: [&str; 6] = ["Habit", "Scheduled", "Done", "Hit rate", "Streak", "Amount"];
+
+pub fn render(period: Period, rows: &[ReportRow], layout: Layout) -> String {
+    let cells: Vec<Vec<String>> = rows.iter().map(cells).collect();
+    let title = format!(
+        "{} ({} to {})",
+        period.title(),
+        period.start(),
+        period.end()
+    );
+    let total = summary(rows);
+    match layout {
+        Layout::Text => {
+            let headers: Vec<String> = HEADERS.iter().map(|h| h.to_uppercase()).collect();
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+            format!(
+                "📈 {}\n{}\n{}",
+                title,
+                table::render(&headers, &cells),
+                total
+            )
+        }
+        Layout::Markdown => {
+            let mut out = format!("## Habit report: {}\n\n", title);
+            out.push_str(&format!("| {} |\n", HEADERS.join(" | ")));
+            out.push_str("|---|---:|---:|---:|---|---:|\n");
+            for row in &cells {
+                let row: Vec<String> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+                out.push_str(&format!("| {} |\n", row.join(" | ")));
+            }
+            out.push_str(&format!("\n**Total:** {}", total));
+            out
+        }
+        Layout::Html => {
+            let mut out = format!("<h2>Habit report: {}</h2>\n<table>\n", escape(&title));
+            out.push_str("  <thead>\n    <tr>");
+            for header in HEADERS {
+                out.push_str(&format!("<th>{}</th>", header));
+            }
+            out.push_str("</tr>\n  </thead>\n  <tbody>\n");
+            for row in &cells {
+                out.push_str("    <tr>");
+                for cell in row {
+                    out.push_str(&format!("<td>{}</td>", escape(cell)));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("  </tbody>\n</table>\n");
+            out.push_str(&format!(
+                "<p><strong>Total:</strong> {}</p>",
+                escape(&total)
+            ));
+            out
+        }
+    }
+}
+
+fn cells(row: &ReportRow) -> Vec<String> {
+    let change = i64::from(row.streak_after) - i64::from(row.streak_before);
+    vec![
+        row.name.clone(),
+        row.scheduled.to_string(),
+        row.completed.to_string(),
+        row.hit_rate
+            .map_or("-".into(), |r| format!("{:.0}%", r * 100.0)),
+        format!(
+            "{} → {} {}{}",
+            row.streak_before,
+            row.streak_after,
+            row.streak_unit.label(row.streak_after),
+            match change {
+                0 => String::new(),
+                c => format!(" ({:+})", c),
+            }
+        ),
+        match (row.amount, &row.unit) {
+            (Some(amount), Some(unit)) => format!("{} {}", amount, unit),
+            _ => String::new(),
+        },
+    ]
+}
+
+fn summary(rows: &[ReportRow]) -> String {
+    let scheduled: u32 = rows.iter().map(|r| r.scheduled).sum();
+    let completed: u32 = rows.iter().map(|r| r.completed).sum();
+    let on_target = rows
+        .iter()
+        .filter(|r| r.scheduled > 0 && r.completed >= r.scheduled)
+        .count();
+    let rate = if scheduled > 0 {
+        format!(
+            " ({:.0}%)",
+            f64::from(completed) / f64::from(scheduled) * 100.0
+        )
+    } else {
+        String::new()
+    };
+    format!(
+        "{} of {} scheduled done{}, {} of {} habits on target",
+        completed,
+        scheduled,
+        rate,
+        on_target,
+        rows.len()
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,193 @@
+use crate::cli::views::HabitView;
+use crate::error::{HabitError, Result};
+use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
+
+/// A column of the `habit list` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Name,
+    Schedule,
+    Today,
+    Streak,
+    Rate,
+    Progress,
+    Created,
+    Tags,
+    Description,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 5] = [
+        Column::Name,
+        Column::Schedule,
+        Column::Today,
+        Column::Streak,
+        Column::Rate,
+    ];
+    const NAMES: &'static str =
+        "id, name, schedule, today, streak, rate, progress, created, tags or description";
+
+    pub fn header(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Name => "NAME",
+            Column::Schedule => "SCHEDULE",
+            Column::Today => "TODAY",
+            Column::Streak => "STREAK",
+            Column::Rate => "RATE",
+            Column::Progress => "PROGRESS",
+            Column::Created => "CREATED",
+            Column::Tags => "TAGS",
+            Column::Description => "DESCRIPTION",
+        }
+    }
+
+    pub fn cell(self, habit: &HabitView) -> String {
+        match self {
+            Column::Id => habit.id.to_string(),
+            Column::Name => habit.name.clone(),
+            Column::Schedule => habit.schedule.clone(),
+            Column::Today => match (habit.target, &habit.unit) {
+                (Some(target), Some(unit)) => {
+                    format!("{}/{} {}", habit.today_total, target, unit)
+                }
+                _ if habit.done_today => "done".into(),
+                _ if habit.paused => "paused".into(),
+                _ if habit.due_today => "due".into(),
+                _ => "-".into(),
+            },
+            Column::Streak => format!(
+                "{} {}{}",
+                habit.streak.current,
+                habit.streak.unit.label(habit.streak.current),
+                if habit.streak.at_risk { " !" } else { "" }
+            ),
+            Column::Rate => habit
+                .rate
+                .map_or("-".into(), |r| format!("{:.0}%", r * 100.0)),
+            Column::Progress => format!(
+                "{}/{} this {}",
+                habit.progress.done,
+                habit.progress.target,
+                habit.progress.period.label(1)
+            ),
+            Column::Created => habit.created.to_string(),
+            Column::Tags => habit.tags.join(","),
+            Column::Description => habit.description.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "id" => Ok(Column::Id),
+            "name" => Ok(Column::Name),
+            "schedule" => Ok(Column::Schedule),
+            "today" => Ok(Column::Today),
+            "streak" => Ok(Column::Streak),
+            "rate" => Ok(Column::Rate),
+            "progress" => Ok(Column::Progress),
+            "created" => Ok(Column::Created),
+            "tags" => Ok(Column::Tags),
+            "description" => Ok(Column::Description),
+            _ => Err(HabitError::UnknownColumn(s.to_string(), Column::NAMES)),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header().to_ascii_lowercase())
+    }
+}
+
+/// How `habit list` orders habits. Without one, habits keep the store order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Created,
+    Streak,
+    Rate,
+    Due,
+}
+
+impl SortKey {
+    const NAMES: &'static str = "name, created, streak, rate or due";
+
+    /// Sorts best-first for streak and rate, due habits first for due.
+    pub fn sort(self, habits: &mut [HabitView]) {
+        match self {
+            SortKey::Name => habits.sort_by_key(|h| h.name.to_lowercase()),
+            SortKey::Created => habits.sort_by_key(|h| h.created),
+            SortKey::Streak => {
+                habits.sort_by_key(|h| Reverse((h.streak.current, h.streak.longest)))
+            }
+            // habits without a rate yet go last
+            SortKey::Rate => habits.sort_by(|a, b| match (b.rate, a.rate) {
+                (Some(b), Some(a)) => b.total_cmp(&a),
+                (b, a) => b.is_some().cmp(&a.is_some()),
+            }),
+            SortKey::Due => habits.sort_by_key(|h| (!h.due_today, h.done_today)),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "name" => Ok(SortKey::Name),
+            "created" => Ok(SortKey::Created),
+            "streak" => Ok(SortKey::Streak),
+            "rate" => Ok(SortKey::Rate),
+            "due" => Ok(SortKey::Due),
+            _ => Err(HabitError::UnknownSort(s.to_string(), SortKey::NAMES)),
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortKey::Name => write!(f, "name"),
+            SortKey::Created => write!(f, "created"),
+            SortKey::Streak => write!(f, "streak"),
+            SortKey::Rate => write!(f, "rate"),
+            SortKey::Due => write!(f, "due"),
+        }
+    }
+}
+
+/// Left-aligned columns separated by two spaces, with a header row.
+pub fn render(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &mut dyn Iterator<Item = &str>| {
+        let mut out = String::new();
+        for (i, (cell, width)) in cells.zip(&widths).enumerate() {
+            if i + 1 == widths.len() {
+                out.push_str(cell);
+            } else {
+                out.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+        }
+        out.trim_end().to_string()
+    };
+    let mut out = line(&mut headers.iter().copied());
+    for row in rows {
+        out.push('\n');
+        out.push_str(&line(&mut row.iter().map(String::as_str)));
+    }
+    out
+}
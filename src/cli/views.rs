@@ -0,0 +1,362 @@
+//! The stable shapes printed by `--format json|csv|tsv`, documented in
+//! `docs/output.md`. Field names and order are part of the interface.
+
+use crate::models::analytics::HabitStats;
+use crate::models::habit::{Habit, Pause, Progress};
+use crate::models::schedule::PeriodKind;
+use crate::models::streak::{StreakRun, StreakSummary};
+use crate::models::timezone::Zone;
+use crate::storage::diff::HabitChange;
+use crate::storage::journal::{EntryKind, JournalEntry};
+use crate::storage::json_storage::HabitStore;
+use crate::storage::validation::{Issue, IssueKind, Severity};
+use crate::transfer::import::ImportSummary;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HabitView {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub created: NaiveDate,
+    pub schedule: String,
+    pub target: Option<f64>,
+    pub unit: Option<String>,
+    pub active: bool,
+    pub due_today: bool,
+    pub today_total: f64,
+    pub progress: Progress,
+    pub completed_days: usize,
+    pub streak: StreakView,
+    pub done_today: bool,
+    /// `null` until the schedule has asked for anything.
+    pub rate: Option<f64>,
+    pub tags: Vec<String>,
+    /// Whether today is paused.
+    pub paused: bool,
+    pub pauses: Vec<Pause>,
+}
+
+impl HabitView {
+    pub fn new(habit: &Habit, today: NaiveDate, zone: &Zone) -> Self {
+        Self {
+            id: habit.id,
+            name: habit.name.clone(),
+            description: habit.description.clone(),
+            created: habit.created_day(zone),
+            schedule: habit.schedule.to_string(),
+            target: habit.goal.as_ref().map(|g| g.target),
+            unit: habit.goal.as_ref().map(|g| g.unit.clone()),
+            active: habit.is_active,
+            due_today: habit.is_due(today, zone),
+            today_total: habit.day_total(today, zone),
+            progress: habit.progress(today, zone),
+            completed_days: habit.completed_days(zone).len(),
+            streak: StreakView::from(&habit.streaks(today, zone)),
+            done_today: habit.completed_days(zone).contains(&today),
+            rate: habit.completion_rate(today, zone),
+            tags: habit.tags.clone(),
+            paused: habit.is_paused(today),
+            pauses: habit.pauses.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreakView {
+    pub unit: PeriodKind,
+    pub current: u32,
+    pub longest: u32,
+    pub at_risk: bool,
+}
+
+impl From<&StreakSummary> for StreakView {
+    fn from(s: &StreakSummary) -> Self {
+        Self {
+            unit: s.unit,
+            current: s.current,
+            longest: s.longest,
+            at_risk: s.at_risk,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreakRow {
+    pub rank: usize,
+    pub id: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub streak: StreakView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history: Option<Vec<StreakRun>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionView {
+    pub id: Uuid,
+    pub name: String,
+    pub added: Vec<NaiveDate>,
+    pub already_completed: Vec<NaiveDate>,
+    /// Set only when logging an amount towards a daily target.
+    pub amount: Option<f64>,
+    pub day_total: Option<f64>,
+    pub target: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsView {
+    pub id: Uuid,
+    pub name: String,
+    pub schedule: String,
+    #[serde(flatten)]
+    pub stats: HabitStats,
+}
+
+/// One habit's line in `habit report`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportRow {
+    pub period: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub id: Uuid,
+    pub name: String,
+    pub schedule: String,
+    pub scheduled: u32,
+    pub completed: u32,
+    pub hit_rate: Option<f64>,
+    pub streak_unit: PeriodKind,
+    pub streak_before: u32,
+    pub streak_after: u32,
+    /// Logged during the period; only for measurable habits.
+    pub amount: Option<f64>,
+    pub unit: Option<String>,
+}
+
+/// One day of `habit calendar`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CalendarDayView {
+    pub date: NaiveDate,
+    /// Share of what was expected that got done; `null` when nothing was.
+    pub ratio: Option<f64>,
+    pub level: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HabitRef {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl From<&Habit> for HabitRef {
+    fn from(h: &Habit) -> Self {
+        Self {
+            id: h.id,
+            name: h.name.clone(),
+        }
+    }
+}
+
+/// Printed by `pause` and `resume`: the habit's pauses after the change.
+#[derive(Debug, Clone, Serialize)]
+pub struct PauseView {
+    pub id: Uuid,
+    pub name: String,
+    pub paused: bool,
+    pub pauses: Vec<Pause>,
+}
+
+impl PauseView {
+    pub fn new(habit: &Habit, today: NaiveDate) -> Self {
+        Self {
+            id: habit.id,
+            name: habit.name.clone(),
+            paused: habit.is_paused(today),
+            pauses: habit.pauses.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacationView {
+    pub from: NaiveDate,
+    pub until: Option<NaiveDate>,
+    /// Habits the vacation pauses.
+    pub habits: usize,
+}
+
+impl VacationView {
+    pub fn new(store: &HabitStore, vacation: Pause) -> Self {
+        Self {
+            from: vacation.from,
+            until: vacation.until,
+            habits: store
+                .habits
+                .iter()
+                .filter(|h| h.pauses.contains(&vacation))
+                .count(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UncompleteView {
+    pub id: Uuid,
+    pub name: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationView {
+    pub target: String,
+    pub habits: usize,
+    pub completions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupView {
+    pub number: usize,
+    pub id: String,
+    pub taken_at: DateTime<Utc>,
+    pub path: String,
+    /// `null` when the backup file cannot be read.
+    pub habits: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeView {
+    pub change: &'static str,
+    pub id: Uuid,
+    pub name: String,
+    pub summary: String,
+}
+
+impl From<&HabitChange<'_>> for ChangeView {
+    fn from(change: &HabitChange<'_>) -> Self {
+        let (kind, habit) = match change {
+            HabitChange::Added(h) => ("added", h),
+            HabitChange::Removed(h) => ("removed", h),
+            HabitChange::Modified { after, .. } => ("modified", after),
+        };
+        Self {
+            change: kind,
+            id: habit.id,
+            name: habit.name.clone(),
+            summary: change.describe(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreView {
+    pub backup: String,
+    pub applied: bool,
+    pub settings_changed: bool,
+    pub changes: Vec<ChangeView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportView {
+    pub files: Vec<String>,
+    pub habits: usize,
+    pub completions: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportView {
+    pub files: Vec<String>,
+    pub dry_run: bool,
+    #[serde(flatten)]
+    pub summary: ImportSummary,
+    /// What the source had that was left out or approximated.
+    pub unmapped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntryView {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub kind: &'static str,
+    /// The entry an undo or redo refers to.
+    pub of: Option<u64>,
+    pub command: String,
+    pub undone: bool,
+    pub events: Vec<String>,
+}
+
+impl JournalEntryView {
+    pub fn new(entry: &JournalEntry, undone: bool) -> Self {
+        let (kind, of) = match entry.kind {
+            EntryKind::Baseline => ("baseline", None),
+            EntryKind::Command => ("command", None),
+            EntryKind::Undo { of } => ("undo", Some(of)),
+            EntryKind::Redo { of } => ("redo", Some(of)),
+        };
+        Self {
+            seq: entry.seq,
+            at: entry.at,
+            kind,
+            of,
+            command: entry.command.clone(),
+            undone,
+            events: entry.events.iter().map(|e| e.describe()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyView {
+    pub entries: usize,
+    pub consistent: bool,
+    pub settings_differ: bool,
+    pub differences: Vec<ChangeView>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IssueView {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub habit: Uuid,
+    pub message: String,
+    pub fixed: bool,
+}
+
+impl IssueView {
+    pub fn new(issue: &Issue, fixed: bool) -> Self {
+        Self {
+            severity: issue.severity(),
+            kind: issue.kind,
+            habit: issue.habit,
+            message: issue.message.clone(),
+            fixed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateView {
+    pub source: String,
+    pub detail: String,
+    pub habits: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveryView {
+    /// `null` when the store loads and nothing needs recovering.
+    pub source: Option<String>,
+    pub detail: String,
+    pub habits: usize,
+    pub candidates: Vec<CandidateView>,
+    pub lost: Vec<String>,
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigView {
+    pub timezone: Option<String>,
+    pub effective_timezone: String,
+    pub today: NaiveDate,
+    pub updated: bool,
+}
@@ -0,0 +1,47 @@
+use crate::error::Result;
+use crate::storage::backup::BackupPolicy;
+use crate::storage::lock::DEFAULT_LOCK_TIMEOUT;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Tool-level configuration, read from `HABIT_CONFIG` or `habit.config.json`
+/// in the working directory. Every field is optional; a missing file means
+/// defaults everywhere.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Storage location, e.g. `habits.json` or `sqlite:///home/me/habits.db`.
+    /// `HABIT_STORAGE` takes precedence.
+    pub storage: Option<String>,
+    /// How long to wait for another process holding the store lock.
+    pub lock_timeout_secs: Option<f64>,
+    /// Retention of the automatic backups taken before every save.
+    pub backups: BackupPolicy,
+}
+
+impl Config {
+    pub fn lock_timeout(&self) -> Duration {
+        self.lock_timeout_secs
+            .and_then(|s| Duration::try_from_secs_f64(s).ok())
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT)
+    }
+
+    pub fn load() -> Result<Self> {
+        let path = config_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+}
+
+fn config_path() -> PathBuf {
+    if let Ok(custom) = env::var("HABIT_CONFIG") {
+        return PathBuf::from(custom);
+    }
+    PathBuf::from("habit.config.json")
+}
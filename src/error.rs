@@ -1,17 +1,110 @@
+use chrono::NaiveDate;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum HabitError {
     #[error("habit not found: {0}")]
     NotFound(String),
+    #[error("habit already exists: {0}")]
+    AlreadyExists(String),
     #[error("invalid habit name: {0}")]
     InvalidName(String),
+    #[error("invalid tag: {0:?} (tags are non-empty and contain no commas or spaces)")]
+    InvalidTag(String),
+    #[error(
+        "invalid schedule: {0} (try 'daily', 'mon,wed,fri', '3/week', 'every 2 days', '4/month' or 'days 1,15')"
+    )]
+    InvalidSchedule(String),
+    #[error("invalid date: {0} (use YYYY-MM-DD, 'today', 'yesterday' or -Nd)")]
+    InvalidDate(String),
+    #[error("invalid period: {0} (use a week like 2026-W41, a month like 2026-10, or 'last')")]
+    InvalidPeriod(String),
+    #[error("invalid import: {0}")]
+    InvalidImport(String),
+    #[error("{0} is in the future (use --force to log it anyway)")]
+    FutureDate(NaiveDate),
+    #[error("{0} is before the habit was created (use --force to log it anyway)")]
+    BeforeCreated(NaiveDate),
+    #[error("invalid timezone: {0} (use an IANA name like Asia/Kolkata or an offset like +05:30)")]
+    InvalidTimezone(String),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
     #[error("habit already completed for date: {0}")]
     AlreadyCompleted(String),
+    #[error("habit not completed for date: {0}")]
+    NotCompleted(String),
+    #[error("habit already paused: {0}")]
+    AlreadyPaused(String),
+    #[error("habit is not paused: {0}")]
+    NotPaused(String),
+    #[error("store is locked by another habit process ({0}); retry or raise lock_timeout_secs")]
+    Locked(String),
+    #[error("unsupported store schema version: {0}; upgrade habit to open this file")]
+    UnsupportedVersion(String),
+    #[error("store is damaged ({0}); run `habit recover` to restore it")]
+    Corrupt(String),
+    #[error("cannot apply journal entry: {0}")]
+    JournalConflict(String),
+    #[error("backup not found: {0} (see `habit backup list`)")]
+    BackupNotFound(String),
+    #[error("unsupported output format: {0} (use text, json, csv or tsv)")]
+    UnsupportedFormat(String),
+    #[error("unknown column: {0} (use {1})")]
+    UnknownColumn(String, &'static str),
+    #[error("unknown sort key: {0} (use {1})")]
+    UnknownSort(String, &'static str),
+    #[error("storage backend unavailable: {0}")]
+    BackendUnavailable(String),
+    #[error("migration to {0} did not round-trip; the source was left untouched")]
+    MigrationFailed(String),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Database(#[from] rusqlite::Error),
+}
+
+impl HabitError {
+    /// Stable identifier for scripts, reported with `--format json` errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            HabitError::NotFound(_) => "not_found",
+            HabitError::AlreadyExists(_) => "already_exists",
+            HabitError::InvalidName(_) => "invalid_name",
+            HabitError::InvalidTag(_) => "invalid_tag",
+            HabitError::InvalidSchedule(_) => "invalid_schedule",
+            HabitError::InvalidDate(_) => "invalid_date",
+            HabitError::InvalidPeriod(_) => "invalid_period",
+            HabitError::InvalidImport(_) => "invalid_import",
+            HabitError::FutureDate(_) => "future_date",
+            HabitError::BeforeCreated(_) => "before_created",
+            HabitError::InvalidTimezone(_) => "invalid_timezone",
+            HabitError::InvalidAmount(_) => "invalid_amount",
+            HabitError::AlreadyCompleted(_) => "already_completed",
+            HabitError::NotCompleted(_) => "not_completed",
+            HabitError::AlreadyPaused(_) => "already_paused",
+            HabitError::NotPaused(_) => "not_paused",
+            HabitError::Locked(_) => "locked",
+            HabitError::UnsupportedVersion(_) => "unsupported_version",
+            HabitError::Corrupt(_) => "corrupt",
+            HabitError::JournalConflict(_) => "journal_conflict",
+            HabitError::BackupNotFound(_) => "backup_not_found",
+            HabitError::UnsupportedFormat(_) => "unsupported_format",
+            HabitError::UnknownColumn(..) => "unknown_column",
+            HabitError::UnknownSort(..) => "unknown_sort",
+            HabitError::BackendUnavailable(_) => "backend_unavailable",
+            HabitError::MigrationFailed(_) => "migration_failed",
+            HabitError::Io(_) => "io",
+            HabitError::Csv(_) => "csv",
+            HabitError::Serde(_) => "serialization",
+            #[cfg(feature = "sqlite")]
+            HabitError::Database(_) => "database",
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, HabitError>;
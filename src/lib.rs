@@ -1,10 +1,46 @@
 pub mod models {
+    pub mod analytics;
     pub mod habit;
+    pub mod schedule;
+    pub mod streak;
+    pub mod timezone;
 }
 pub mod storage {
+    pub mod backend;
+    pub mod backup;
+    pub mod diff;
+    pub mod journal;
     pub mod json_storage;
+    pub mod lock;
+    pub mod migrations;
+    pub mod recovery;
+    pub mod repository;
+    #[cfg(feature = "sqlite")]
+    pub mod sqlite_storage;
+    pub mod validation;
 }
 pub mod cli {
+    pub mod checkin;
     pub mod commands;
+    pub mod heatmap;
+    pub mod output;
+    pub mod report;
+    pub mod table;
+    pub mod views;
 }
+pub mod transfer {
+    pub mod csv;
+    pub mod habitica;
+    pub mod ics;
+    pub mod import;
+    pub mod loop_tracker;
+}
+#[cfg(feature = "tui")]
+pub mod tui {
+    pub mod app;
+    pub mod terminal;
+    pub mod ui;
+}
+pub mod config;
 pub mod error;
+pub mod utils;
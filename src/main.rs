@@ -1,10 +1,24 @@
 use clap::Parser;
 use habit::cli::commands::{Cli, run};
+use habit::cli::output::Output;
+use habit::storage::backend::open_repository;
 
 fn main() {
-    let cli = Cli::parse();
-    if let Err(err) = run(cli) {
-        eprintln!("❌ Error: {}", err);
+    let mut cli = Cli::parse();
+    cli.invocation = std::env::args()
+        .skip(1)
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("\"{}\"", arg)
+            } else {
+                arg
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let out = Output::new(cli.format);
+    if let Err(err) = open_repository().and_then(|mut repo| run(cli, repo.as_mut())) {
+        out.error(&err);
         std::process::exit(1);
     }
 }
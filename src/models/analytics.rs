@@ -0,0 +1,233 @@
+use crate::models::habit::Habit;
+use crate::models::timezone::Zone;
+use chrono::{Datelike, Days, NaiveDate, NaiveTime, Timelike, Weekday};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::f64::consts::TAU;
+
+/// Rates move by at least this much before a trend counts as a change.
+const TREND_THRESHOLD: f64 = 0.05;
+
+/// Everything `habit stats` reports about one habit.
+#[derive(Debug, Clone, Serialize)]
+pub struct HabitStats {
+    pub rates: WindowRates,
+    pub trend: Option<Trend>,
+    pub best_weekday: Option<WeekdayRate>,
+    pub worst_weekday: Option<WeekdayRate>,
+    /// Mean local time of check-ins, leaving out backdated ones.
+    pub average_time: Option<NaiveTime>,
+    pub longest_gap: Option<Gap>,
+    /// Only for measurable habits.
+    pub totals: Option<Totals>,
+}
+
+/// Completion rates against the schedule over trailing windows ending
+/// today, counted like [`Habit::completion_rate`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct WindowRates {
+    pub last_7_days: Option<f64>,
+    pub last_30_days: Option<f64>,
+    pub last_90_days: Option<f64>,
+    pub last_365_days: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Improving,
+    Steady,
+    Declining,
+}
+
+/// The last 30 days against the 30 before them.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Trend {
+    pub direction: Direction,
+    pub current: f64,
+    pub previous: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct WeekdayRate {
+    pub weekday: Weekday,
+    pub rate: f64,
+}
+
+/// The longest run of days without a completion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Gap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Totals {
+    pub unit: String,
+    pub total: f64,
+    pub last_30_days: f64,
+    /// Mean amount on days anything was logged.
+    pub average: f64,
+    pub best_day: Option<NaiveDate>,
+    pub best_amount: f64,
+}
+
+impl HabitStats {
+    pub fn for_habit(habit: &Habit, today: NaiveDate, zone: &Zone) -> Self {
+        let previous_end = today - Days::new(30);
+        let trend = rate(habit, today, 30, today, zone)
+            .zip(rate(habit, previous_end, 30, today, zone))
+            .map(|(current, previous)| Trend {
+                direction: if current - previous >= TREND_THRESHOLD {
+                    Direction::Improving
+                } else if previous - current >= TREND_THRESHOLD {
+                    Direction::Declining
+                } else {
+                    Direction::Steady
+                },
+                current,
+                previous,
+            });
+        let weekdays = weekday_rates(habit, today, zone);
+        Self {
+            rates: WindowRates {
+                last_7_days: rate(habit, today, 7, today, zone),
+                last_30_days: rate(habit, today, 30, today, zone),
+                last_90_days: rate(habit, today, 90, today, zone),
+                last_365_days: rate(habit, today, 365, today, zone),
+            },
+            trend,
+            // ties go to the earlier weekday
+            best_weekday: weekdays
+                .iter()
+                .copied()
+                .reduce(|best, w| if w.rate > best.rate { w } else { best }),
+            worst_weekday: weekdays
+                .iter()
+                .copied()
+                .reduce(|worst, w| if w.rate < worst.rate { w } else { worst }),
+            average_time: average_time(habit, zone),
+            longest_gap: longest_gap(habit, today, zone),
+            totals: totals(habit, today, zone),
+        }
+    }
+}
+
+/// Completion rate over the `days` days ending on `end`.
+fn rate(habit: &Habit, end: NaiveDate, days: u64, today: NaiveDate, zone: &Zone) -> Option<f64> {
+    if end < habit.created_day(zone) {
+        return None;
+    }
+    let (done, expected) = habit.schedule_counts(end - Days::new(days - 1), end, today, zone);
+    (expected > 0).then(|| f64::from(done) / f64::from(expected))
+}
+
+/// Share of each weekday's scheduled days that were done, for weekdays the
+/// schedule has asked for so far. Today counts only once done.
+fn weekday_rates(habit: &Habit, today: NaiveDate, zone: &Zone) -> Vec<WeekdayRate> {
+    let done = habit.counted_days(zone);
+    let mut counts = [(0u32, 0u32); 7];
+    for day in habit
+        .created_day(zone)
+        .iter_days()
+        .take_while(|d| *d <= today)
+    {
+        if !habit.is_scheduled(day, zone) || (day == today && !done.contains(&day)) {
+            continue;
+        }
+        let count = &mut counts[day.weekday().num_days_from_monday() as usize];
+        count.0 += u32::from(done.contains(&day));
+        count.1 += 1;
+    }
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, expected))| *expected > 0)
+        .map(|(i, (done, expected))| WeekdayRate {
+            weekday: Weekday::try_from(i as u8).unwrap_or(Weekday::Mon),
+            rate: f64::from(*done) / f64::from(*expected),
+        })
+        .collect()
+}
+
+/// Circular mean of check-in times, so 23:00 and 01:00 average to midnight.
+/// Backdated completions sit at exactly local midday and are skipped.
+fn average_time(habit: &Habit, zone: &Zone) -> Option<NaiveTime> {
+    let noon = NaiveTime::from_hms_opt(12, 0, 0)?;
+    let angles: Vec<f64> = habit
+        .completions
+        .iter()
+        .map(|c| zone.time_of(c.at))
+        .filter(|t| *t != noon)
+        .map(|t| f64::from(t.num_seconds_from_midnight()) / 86_400.0 * TAU)
+        .collect();
+    if angles.is_empty() {
+        return None;
+    }
+    let (sin, cos) = angles
+        .iter()
+        .fold((0.0, 0.0), |(s, c), a| (s + a.sin(), c + a.cos()));
+    let turn = sin.atan2(cos).rem_euclid(TAU) / TAU;
+    let minutes = (turn * 1440.0).round() as u32 % 1440;
+    NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0)
+}
+
+/// Longest stretch between creation, completed or paused days and yesterday
+/// with no completion. Today is left out since it is not over yet.
+fn longest_gap(habit: &Habit, today: NaiveDate, zone: &Zone) -> Option<Gap> {
+    let created = habit.created_day(zone);
+    let mut bounds: Vec<NaiveDate> = habit
+        .completed_days(zone)
+        .into_iter()
+        .chain(
+            created
+                .iter_days()
+                .take_while(|d| *d < today)
+                .filter(|d| habit.is_paused(*d)),
+        )
+        .filter(|d| *d >= created && *d < today)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    // sentinels just outside the range, so leading and trailing gaps count
+    bounds.insert(0, created - Days::new(1));
+    bounds.push(today);
+    bounds
+        .windows(2)
+        .filter(|w| (w[1] - w[0]).num_days() > 1)
+        .map(|w| Gap {
+            from: w[0] + Days::new(1),
+            to: w[1] - Days::new(1),
+            days: ((w[1] - w[0]).num_days() - 1) as u32,
+        })
+        .reduce(|longest, g| if g.days > longest.days { g } else { longest })
+}
+
+fn totals(habit: &Habit, today: NaiveDate, zone: &Zone) -> Option<Totals> {
+    let goal = habit.goal.as_ref()?;
+    let days = habit.day_totals(zone);
+    let total = days.values().fold(0.0, |sum, amount| sum + amount);
+    let since = today - Days::new(29);
+    let best = days.iter().fold(
+        None,
+        |best: Option<(&NaiveDate, &f64)>, (day, amount)| match best {
+            Some((_, most)) if most >= amount => best,
+            _ => Some((day, amount)),
+        },
+    );
+    Some(Totals {
+        unit: goal.unit.clone(),
+        total,
+        last_30_days: days
+            .range(since..=today)
+            .fold(0.0, |sum, (_, amount)| sum + amount),
+        average: if days.is_empty() {
+            0.0
+        } else {
+            total / days.len() as f64
+        },
+        best_day: best.map(|(day, _)| *day),
+        best_amount: best.map_or(0.0, |(_, amount)| *amount),
+    })
+}
@@ -1,28 +1,137 @@
-use chrono::{DateTime, Utc};
+use crate::error::{HabitError, Result};
+use crate::models::schedule::{PeriodKind, Schedule};
+use crate::models::streak::StreakSummary;
+use crate::models::timezone::Zone;
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Habit {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
-    pub completions: Vec<DateTime<Utc>>,
-    pub target_frequency: Option<u32>,
+    pub completions: Vec<Completion>,
+    pub schedule: Schedule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Goal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub is_active: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pauses: Vec<Pause>,
+}
+
+/// A daily target for a measurable habit, e.g. 8 glasses or 5 km.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Goal {
+    pub target: f64,
+    pub unit: String,
+}
+
+/// Days a habit is on hold, which count as neither missed nor done.
+/// `until` is the last paused day; without it the pause lasts until the
+/// habit is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pause {
+    pub from: NaiveDate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    /// Added to every habit at once by `habit vacation`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub vacation: bool,
+}
+
+impl Pause {
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.from <= day && self.until.is_none_or(|until| day <= until)
+    }
+
+    /// The first day both pauses cover, if they share any.
+    pub fn overlap(&self, other: &Pause) -> Option<NaiveDate> {
+        let first = self.from.max(other.from);
+        (self.contains(first) && other.contains(first)).then_some(first)
+    }
+}
+
+/// One check-in. Plain completions carry no amount or note and are stored
+/// as a bare timestamp, the same as before measurable habits existed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "CompletionRecord", into = "CompletionRecord")]
+pub struct Completion {
+    pub at: DateTime<Utc>,
+    pub amount: Option<f64>,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CompletionRecord {
+    At(DateTime<Utc>),
+    Entry {
+        at: DateTime<Utc>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        amount: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+}
+
+impl From<CompletionRecord> for Completion {
+    fn from(r: CompletionRecord) -> Self {
+        match r {
+            CompletionRecord::At(at) => Completion::new(at),
+            CompletionRecord::Entry { at, amount, note } => Completion { at, amount, note },
+        }
+    }
+}
+
+impl From<Completion> for CompletionRecord {
+    fn from(c: Completion) -> Self {
+        match (c.amount, c.note) {
+            (None, None) => CompletionRecord::At(c.at),
+            (amount, note) => CompletionRecord::Entry {
+                at: c.at,
+                amount,
+                note,
+            },
+        }
+    }
+}
+
+impl Completion {
+    pub fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            at,
+            amount: None,
+            note: None,
+        }
+    }
+}
+
+/// Completions counted against the schedule within one period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    pub period: PeriodKind,
+    pub done: u32,
+    pub target: u32,
 }
 
 impl Habit {
-    pub fn new(name: String, description: Option<String>, target_frequency: Option<u32>) -> Self {
+    pub fn new(name: String, description: Option<String>, schedule: Schedule) -> Self {
         Self {
             id: Uuid::new_v4(),
             name,
             description,
             created_at: Utc::now(),
             completions: Vec::new(),
-            target_frequency,
+            schedule,
+            goal: None,
+            tags: Vec::new(),
             is_active: true,
+            pauses: Vec::new(),
         }
     }
 
@@ -30,20 +139,373 @@ impl Habit {
         &self.name
     }
 
-    pub fn mark_complete(&mut self, date: DateTime<Utc>) -> bool {
-        let exists = self
-            .completions
-            .iter()
-            .any(|d| d.date_naive() == date.date_naive());
-        if exists {
-            return false;
-        }
-        self.completions.push(date);
-        self.completions.sort();
+    /// Records a completion at `date` unless the local day in `zone` is
+    /// already complete. For measurable habits the entry tops the day up to
+    /// its target.
+    pub fn mark_complete(&mut self, date: DateTime<Utc>, zone: &Zone) -> bool {
+        let day = zone.date_of(date);
+        let amount = match &self.goal {
+            Some(goal) => {
+                let remaining = goal.target - self.day_total(day, zone);
+                if remaining <= 0.0 {
+                    return false;
+                }
+                Some(remaining)
+            }
+            None if self.completions.iter().any(|c| zone.date_of(c.at) == day) => return false,
+            None => None,
+        };
+        self.push_completion(Completion {
+            at: date,
+            amount,
+            note: None,
+        });
         true
     }
 
-    pub fn recent_completions(&self) -> &[DateTime<Utc>] {
+    /// Adds `amount` towards the day containing `date` and returns the day's
+    /// new total.
+    pub fn log_amount(&mut self, date: DateTime<Utc>, amount: f64, zone: &Zone) -> f64 {
+        self.push_completion(Completion {
+            at: date,
+            amount: Some(amount),
+            note: None,
+        });
+        self.day_total(zone.date_of(date), zone)
+    }
+
+    fn push_completion(&mut self, completion: Completion) {
+        self.completions.push(completion);
+        self.completions.sort_by_key(|c| c.at);
+    }
+
+    /// Attaches a note to the completion logged at `at`.
+    pub fn set_note(&mut self, at: DateTime<Utc>, note: &str) {
+        if let Some(c) = self.completions.iter_mut().rfind(|c| c.at == at) {
+            c.note = Some(note.to_string());
+        }
+    }
+
+    /// Removes every completion logged on the same local day as `date`.
+    pub fn unmark_complete(&mut self, date: DateTime<Utc>, zone: &Zone) -> bool {
+        let day = zone.date_of(date);
+        let before = self.completions.len();
+        self.completions.retain(|c| zone.date_of(c.at) != day);
+        self.completions.len() != before
+    }
+
+    pub fn recent_completions(&self) -> &[Completion] {
         &self.completions
     }
+
+    /// Sum of amounts logged on `day`; a plain completion counts as one.
+    pub fn day_total(&self, day: NaiveDate, zone: &Zone) -> f64 {
+        self.completions
+            .iter()
+            .filter(|c| zone.date_of(c.at) == day)
+            .map(|c| c.amount.unwrap_or(1.0))
+            .fold(0.0, |total, amount| total + amount)
+    }
+
+    /// Per-day totals of everything logged, keyed by local day.
+    pub fn day_totals(&self, zone: &Zone) -> BTreeMap<NaiveDate, f64> {
+        let mut totals = BTreeMap::new();
+        for c in &self.completions {
+            *totals.entry(zone.date_of(c.at)).or_insert(0.0) += c.amount.unwrap_or(1.0);
+        }
+        totals
+    }
+
+    /// Days that count as done: any check-in for plain habits, or a total
+    /// meeting the target for measurable ones.
+    pub fn completed_days(&self, zone: &Zone) -> BTreeSet<NaiveDate> {
+        self.day_totals(zone)
+            .into_iter()
+            .filter(|(_, total)| self.goal.as_ref().is_none_or(|g| *total >= g.target))
+            .map(|(day, _)| day)
+            .collect()
+    }
+
+    /// Completed days outside pauses, the ones due dates, streaks and rates
+    /// count.
+    pub fn counted_days(&self, zone: &Zone) -> BTreeSet<NaiveDate> {
+        let mut days = self.completed_days(zone);
+        if !self.pauses.is_empty() {
+            days.retain(|d| !self.is_paused(*d));
+        }
+        days
+    }
+
+    /// How much of `day` was done, from 0 to 1: the share of the daily target
+    /// for measurable habits, all or nothing otherwise.
+    pub fn day_ratio(&self, day: NaiveDate, zone: &Zone) -> f64 {
+        let total = self.day_total(day, zone);
+        match &self.goal {
+            Some(goal) => (total / goal.target).min(1.0),
+            None if total > 0.0 => 1.0,
+            None => 0.0,
+        }
+    }
+
+    pub fn created_day(&self, zone: &Zone) -> NaiveDate {
+        zone.date_of(self.created_at)
+    }
+
+    /// Whether `day` is one the schedule asks for. Paused days never are.
+    pub fn is_scheduled(&self, day: NaiveDate, zone: &Zone) -> bool {
+        !self.is_paused(day) && self.schedule.is_scheduled(day, self.created_day(zone))
+    }
+
+    pub fn is_paused(&self, day: NaiveDate) -> bool {
+        self.pauses.iter().any(|p| p.contains(day))
+    }
+
+    /// The pause covering `day`, ending last if several overlap.
+    pub fn pause_on(&self, day: NaiveDate) -> Option<&Pause> {
+        self.pauses
+            .iter()
+            .filter(|p| p.contains(day))
+            .max_by_key(|p| p.until.unwrap_or(NaiveDate::MAX))
+    }
+
+    /// Ends every pause still running on `today` with yesterday, and drops
+    /// those not begun yet. A vacation cut short this way becomes the
+    /// habit's own pause. Returns whether anything changed.
+    pub fn resume(&mut self, today: NaiveDate) -> bool {
+        let before = self.pauses.clone();
+        self.pauses.retain(|p| p.from < today);
+        for p in &mut self.pauses {
+            if p.contains(today) {
+                p.until = today.pred_opt();
+                p.vacation = false;
+            }
+        }
+        self.pauses != before
+    }
+
+    /// The quota of the week or month containing `day`, shrunk in
+    /// proportion to its days before the habit was created or paused. A
+    /// period with no such days left asks for nothing.
+    pub fn quota_in(&self, period: PeriodKind, day: NaiveDate, zone: &Zone) -> u32 {
+        self.quota_between(period, period.start_of(day), period.end_of(day), zone)
+    }
+
+    /// The share of the quota of the period starting `start` that falls on
+    /// the open days from `start` to `end`, both within that period.
+    fn quota_between(
+        &self,
+        period: PeriodKind,
+        start: NaiveDate,
+        end: NaiveDate,
+        zone: &Zone,
+    ) -> u32 {
+        let quota = self.schedule.quota();
+        let (first, last) = (period.start_of(start), period.end_of(start));
+        let created = self.created_day(zone);
+        if self.pauses.is_empty() && created <= start && (start, end) == (first, last) {
+            return quota;
+        }
+        let days = (last - first).num_days() + 1;
+        let open = created
+            .max(start)
+            .iter_days()
+            .take_while(|d| *d <= end)
+            .filter(|d| !self.is_paused(*d))
+            .count() as i64;
+        (f64::from(quota) * open as f64 / days as f64).round() as u32
+    }
+
+    /// Whether the habit still needs doing on `day`.
+    pub fn is_due(&self, day: NaiveDate, zone: &Zone) -> bool {
+        let days = self.counted_days(zone);
+        match self.schedule.period() {
+            PeriodKind::Day => self.is_scheduled(day, zone) && !days.contains(&day),
+            _ if self.is_paused(day) => false,
+            period => {
+                let start = period.start_of(day);
+                let done = days.range(start..=period.end_of(day)).count() as u32;
+                !days.contains(&day) && done < self.quota_in(period, day, zone)
+            }
+        }
+    }
+
+    /// Progress within the week or month containing `day`.
+    pub fn progress(&self, day: NaiveDate, zone: &Zone) -> Progress {
+        let days = self.counted_days(zone);
+        let period = self.schedule.progress_period();
+        let (start, end) = (period.start_of(day), period.end_of(day));
+        let (done, target) = match self.schedule.period() {
+            PeriodKind::Day => {
+                let scheduled: Vec<NaiveDate> = start
+                    .iter_days()
+                    .take_while(|d| *d <= end)
+                    .filter(|d| self.is_scheduled(*d, zone))
+                    .collect();
+                let done = scheduled.iter().filter(|d| days.contains(d)).count();
+                (done as u32, scheduled.len() as u32)
+            }
+            _ => (
+                days.range(start..=end).count() as u32,
+                self.quota_in(period, day, zone),
+            ),
+        };
+        Progress {
+            period,
+            done,
+            target,
+        }
+    }
+
+    /// Whether a lookup by `name` picks this habit. Only ASCII letters
+    /// fold, so `Ärger` and `ärger` stay two habits.
+    pub fn has_name(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// What [`Habit::has_name`] compares: names with the same key are the
+    /// same to a lookup.
+    pub fn name_key(name: &str) -> String {
+        name.to_ascii_lowercase()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Adds tags not already present, ignoring case.
+    pub fn add_tags(&mut self, tags: Vec<String>) -> Result<()> {
+        for tag in tags {
+            let tag = tag.trim().to_string();
+            if tag.is_empty() || tag.contains(|c: char| c == ',' || c.is_whitespace()) {
+                return Err(HabitError::InvalidTag(tag));
+            }
+            if !self.has_tag(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuses logging `day` when it is in the future or before the habit
+    /// was created, unless `force` is set.
+    pub fn check_loggable(
+        &self,
+        day: NaiveDate,
+        today: NaiveDate,
+        zone: &Zone,
+        force: bool,
+    ) -> Result<()> {
+        if force {
+            return Ok(());
+        }
+        if day > today {
+            return Err(HabitError::FutureDate(day));
+        }
+        if day < self.created_day(zone) {
+            return Err(HabitError::BeforeCreated(day));
+        }
+        Ok(())
+    }
+
+    /// Share of what the schedule asked for since the habit was created that
+    /// was done. Today, or the current week or month for quota schedules,
+    /// only counts once it is met; paused days do not count at all. `None`
+    /// until anything was expected.
+    pub fn completion_rate(&self, today: NaiveDate, zone: &Zone) -> Option<f64> {
+        let (done, expected) = self.schedule_counts(self.created_day(zone), today, today, zone);
+        (expected > 0).then(|| f64::from(done) / f64::from(expected))
+    }
+
+    /// Done and expected counts behind [`Habit::completion_rate`] for the
+    /// days from `from` (or creation, if later) to `to`. Quota periods count
+    /// when they overlap those days.
+    pub fn schedule_counts(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        today: NaiveDate,
+        zone: &Zone,
+    ) -> (u32, u32) {
+        self.counts(from, to, today, zone, false)
+    }
+
+    /// Like [`Habit::schedule_counts`] up to `to` or today, whichever is
+    /// first, but a quota period that crosses `from` or `to` only asks for
+    /// the share of its quota on days inside them, and only completions on
+    /// those days count towards it. Adjacent ranges then split such a
+    /// period instead of both counting it whole.
+    pub fn schedule_counts_within(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        today: NaiveDate,
+        zone: &Zone,
+    ) -> (u32, u32) {
+        self.counts(from, to, today, zone, true)
+    }
+
+    fn counts(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        today: NaiveDate,
+        zone: &Zone,
+        clamp: bool,
+    ) -> (u32, u32) {
+        let days = self.counted_days(zone);
+        let from = from.max(self.created_day(zone));
+        let last = if clamp { to.min(today) } else { to };
+        let (mut done, mut expected) = (0u32, 0u32);
+        match self.schedule.period() {
+            PeriodKind::Day => {
+                for day in from.iter_days().take_while(|d| *d <= last) {
+                    if !self.is_scheduled(day, zone) || (day == today && !days.contains(&day)) {
+                        continue;
+                    }
+                    expected += 1;
+                    done += u32::from(days.contains(&day));
+                }
+            }
+            period => {
+                let mut start = period.start_of(from);
+                while start <= last {
+                    let end = period.end_of(start);
+                    let (lo, hi) = if clamp {
+                        (start.max(from), end.min(to))
+                    } else {
+                        (start, end)
+                    };
+                    let quota = self.quota_between(period, lo, hi, zone);
+                    let met = (days.range(lo..=hi).count() as u32).min(quota);
+                    if quota > 0 && (hi < today || met == quota) {
+                        expected += quota;
+                        done += met;
+                    }
+                    start = end + Days::new(1);
+                }
+            }
+        }
+        (done, expected)
+    }
+
+    pub fn streaks(&self, today: NaiveDate, zone: &Zone) -> StreakSummary {
+        StreakSummary::for_habit(self, today, zone)
+    }
+}
+
+/// A goal of `target` `unit`s, refusing targets that aren't positive and finite.
+pub(crate) fn goal_from(target: f64, unit: String) -> Result<Goal> {
+    if !(target > 0.0 && target.is_finite()) {
+        return Err(HabitError::InvalidAmount(target.to_string()));
+    }
+    Ok(Goal { target, unit })
+}
+
+/// The instant recorded for a completion on `day`: now for today, local midday otherwise.
+pub(crate) fn day_instant(day: NaiveDate, today: NaiveDate, zone: &Zone) -> DateTime<Utc> {
+    if day == today {
+        Utc::now()
+    } else {
+        zone.midday(day)
+    }
 }
@@ -0,0 +1,204 @@
+use crate::error::HabitError;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// When a habit is expected to be done.
+///
+/// Serialized as its textual form (`"daily"`, `"mon,wed,fri"`, `"3/week"`,
+/// `"every 2 days"`, `"4/month"`, `"days 1,15"`), which is also what
+/// `--schedule` accepts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Schedule {
+    #[default]
+    Daily,
+    Weekdays(Vec<Weekday>),
+    TimesPerWeek(u32),
+    EveryNDays(u32),
+    TimesPerMonth(u32),
+    DaysOfMonth(Vec<u32>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodKind {
+    Day,
+    Week,
+    Month,
+}
+
+impl PeriodKind {
+    pub fn label(self, n: u32) -> &'static str {
+        match (self, n) {
+            (PeriodKind::Day, 1) => "day",
+            (PeriodKind::Day, _) => "days",
+            (PeriodKind::Week, 1) => "week",
+            (PeriodKind::Week, _) => "weeks",
+            (PeriodKind::Month, 1) => "month",
+            (PeriodKind::Month, _) => "months",
+        }
+    }
+
+    pub fn start_of(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            PeriodKind::Day => day,
+            PeriodKind::Week => day - Days::new(day.weekday().num_days_from_monday() as u64),
+            PeriodKind::Month => day.with_day(1).unwrap_or(day),
+        }
+    }
+
+    pub fn end_of(self, day: NaiveDate) -> NaiveDate {
+        let start = self.start_of(day);
+        match self {
+            PeriodKind::Day => start,
+            PeriodKind::Week => start + Days::new(6),
+            PeriodKind::Month => start + Months::new(1) - Days::new(1),
+        }
+    }
+}
+
+impl Schedule {
+    /// The equivalent of the legacy `target_frequency` field (days per week).
+    pub fn from_weekly_target(days: u32) -> Self {
+        match days {
+            1..=6 => Schedule::TimesPerWeek(days),
+            _ => Schedule::Daily,
+        }
+    }
+
+    /// The period over which the schedule's goal is counted.
+    ///
+    /// Quota schedules (`3/week`, `4/month`) are satisfied per week or month;
+    /// every other schedule is satisfied day by day on its scheduled days.
+    pub fn period(&self) -> PeriodKind {
+        match self {
+            Schedule::TimesPerWeek(_) => PeriodKind::Week,
+            Schedule::TimesPerMonth(_) => PeriodKind::Month,
+            _ => PeriodKind::Day,
+        }
+    }
+
+    pub fn quota(&self) -> u32 {
+        match self {
+            Schedule::TimesPerWeek(n) | Schedule::TimesPerMonth(n) => *n,
+            _ => 1,
+        }
+    }
+
+    /// Whether `day` is one of the schedule's fixed days. Quota schedules
+    /// accept any day. `anchor` is the first day of an `every N days` cycle.
+    pub fn is_scheduled(&self, day: NaiveDate, anchor: NaiveDate) -> bool {
+        match self {
+            Schedule::Daily | Schedule::TimesPerWeek(_) | Schedule::TimesPerMonth(_) => true,
+            Schedule::Weekdays(days) => days.contains(&day.weekday()),
+            Schedule::EveryNDays(n) => {
+                day >= anchor && (day - anchor).num_days() % i64::from((*n).max(1)) == 0
+            }
+            Schedule::DaysOfMonth(days) => {
+                let last = PeriodKind::Month.end_of(day).day();
+                days.iter().any(|&d| d.min(last) == day.day())
+            }
+        }
+    }
+
+    /// The period used to report progress in `list`: the current week for
+    /// weekly-ish schedules and the current month for monthly ones.
+    pub fn progress_period(&self) -> PeriodKind {
+        match self {
+            Schedule::TimesPerMonth(_) | Schedule::DaysOfMonth(_) => PeriodKind::Month,
+            _ => PeriodKind::Week,
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Daily => write!(f, "daily"),
+            Schedule::Weekdays(days) => {
+                let names: Vec<String> =
+                    days.iter().map(|d| d.to_string().to_lowercase()).collect();
+                write!(f, "{}", names.join(","))
+            }
+            Schedule::TimesPerWeek(n) => write!(f, "{}/week", n),
+            Schedule::EveryNDays(n) => write!(f, "every {} days", n),
+            Schedule::TimesPerMonth(n) => write!(f, "{}/month", n),
+            Schedule::DaysOfMonth(days) => {
+                let list: Vec<String> = days.iter().map(u32::to_string).collect();
+                write!(f, "days {}", list.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_lowercase();
+        let invalid = || HabitError::InvalidSchedule(s.to_string());
+        let number = |n: &str, max: u32| match n.trim().parse::<u32>() {
+            Ok(v) if (1..=max).contains(&v) => Ok(v),
+            _ => Err(invalid()),
+        };
+        match input.as_str() {
+            "daily" | "everyday" | "every day" => return Ok(Schedule::Daily),
+            "weekdays" => {
+                return Ok(Schedule::Weekdays(vec![
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri,
+                ]));
+            }
+            "weekends" => return Ok(Schedule::Weekdays(vec![Weekday::Sat, Weekday::Sun])),
+            _ => {}
+        }
+        if let Some(n) = input.strip_suffix("/week") {
+            return Ok(Schedule::TimesPerWeek(number(n, 7)?));
+        }
+        if let Some(n) = input.strip_suffix("/month") {
+            return Ok(Schedule::TimesPerMonth(number(n, 31)?));
+        }
+        if let Some(rest) = input.strip_prefix("every ") {
+            let n = rest
+                .strip_suffix(" days")
+                .or_else(|| rest.strip_suffix('d'))
+                .ok_or_else(invalid)?;
+            return Ok(Schedule::EveryNDays(number(n, 365)?));
+        }
+        if let Some(rest) = input.strip_prefix("days ") {
+            let mut days = rest
+                .split(',')
+                .map(|d| number(d, 31))
+                .collect::<Result<Vec<_>, _>>()?;
+            days.sort_unstable();
+            days.dedup();
+            return Ok(Schedule::DaysOfMonth(days));
+        }
+        let mut days = input
+            .split(',')
+            .map(|d| d.trim().parse::<Weekday>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        days.sort_by_key(|d| d.num_days_from_monday());
+        days.dedup();
+        Ok(Schedule::Weekdays(days))
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = HabitError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(value: Schedule) -> Self {
+        value.to_string()
+    }
+}
@@ -0,0 +1,147 @@
+use crate::models::habit::Habit;
+use crate::models::schedule::PeriodKind;
+use crate::models::timezone::Zone;
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreakRun {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreakSummary {
+    pub unit: PeriodKind,
+    pub current: u32,
+    pub longest: u32,
+    pub at_risk: bool,
+    pub history: Vec<StreakRun>,
+}
+
+/// One scheduled period (a due day, an ISO week or a month) and whether its
+/// goal was met.
+struct Period {
+    start: NaiveDate,
+    end: NaiveDate,
+    done: u32,
+    needed: u32,
+    first: Option<NaiveDate>,
+    last: Option<NaiveDate>,
+}
+
+impl Period {
+    fn met(&self) -> bool {
+        self.done >= self.needed
+    }
+
+    fn contains(&self, day: NaiveDate) -> bool {
+        self.start <= day && day <= self.end
+    }
+}
+
+impl StreakSummary {
+    pub fn for_habit(habit: &Habit, today: NaiveDate, zone: &Zone) -> Self {
+        let days = habit.counted_days(zone);
+        let created = habit.created_day(zone);
+        let unit = habit.schedule.period();
+        let first_day = days
+            .first()
+            .copied()
+            .map_or(created, |d| d.min(created))
+            .min(today);
+        let periods = build_periods(habit, zone, unit, first_day, today, &days);
+        summarize(unit, &periods, today)
+    }
+}
+
+fn build_periods(
+    habit: &Habit,
+    zone: &Zone,
+    unit: PeriodKind,
+    first_day: NaiveDate,
+    today: NaiveDate,
+    days: &BTreeSet<NaiveDate>,
+) -> Vec<Period> {
+    let mut periods = Vec::new();
+    let mut start = unit.start_of(first_day);
+    while start <= today {
+        let end = unit.end_of(start);
+        let needed = match unit {
+            PeriodKind::Day => u32::from(habit.is_scheduled(start, zone)),
+            _ => habit.quota_in(unit, start, zone),
+        };
+        // days off the schedule and paused periods neither extend nor break a streak
+        if needed > 0 {
+            let mut hits = days.range(start..=end);
+            let done = hits.clone().count() as u32;
+            let first = hits.next().copied();
+            let last = hits.next_back().copied().or(first);
+            periods.push(Period {
+                start,
+                end,
+                done,
+                needed,
+                first,
+                last,
+            });
+        }
+        start = end + Days::new(1);
+    }
+    periods
+}
+
+fn summarize(unit: PeriodKind, periods: &[Period], today: NaiveDate) -> StreakSummary {
+    let mut history = Vec::new();
+    let mut run: Option<StreakRun> = None;
+    for p in periods {
+        if p.met() {
+            let start = p.first.unwrap_or(p.start);
+            let end = p.last.unwrap_or(p.end);
+            match run.as_mut() {
+                Some(r) => {
+                    r.end = end;
+                    r.length += 1;
+                }
+                None => {
+                    run = Some(StreakRun {
+                        start,
+                        end,
+                        length: 1,
+                    })
+                }
+            }
+        } else if let Some(r) = run.take() {
+            history.push(r);
+        }
+    }
+    if let Some(r) = run {
+        history.push(r);
+    }
+    let last = periods.last();
+    let current_met = last.is_some_and(Period::met);
+    // the current period is still in progress, so missing it cannot break a streak yet
+    let in_progress = last.is_some_and(|p| p.contains(today));
+    let carried =
+        !current_met && in_progress && periods.len() >= 2 && periods[periods.len() - 2].met();
+    let current = if current_met || carried {
+        history.last().map_or(0, |r| r.length)
+    } else {
+        0
+    };
+    let at_risk = carried
+        && last.is_some_and(|p| {
+            let days_left = (p.end - today).num_days() as u32 + 1;
+            p.needed - p.done >= days_left
+        });
+    let longest = history.iter().map(|r| r.length).max().unwrap_or(0);
+    StreakSummary {
+        unit,
+        current,
+        longest,
+        at_risk,
+        history,
+    }
+}
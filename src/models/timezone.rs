@@ -0,0 +1,140 @@
+use crate::error::{HabitError, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// The user's timezone, which decides where one day ends and the next
+/// begins. Completions are always stored as UTC instants; the zone is only
+/// applied when turning them into calendar days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Zone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl Default for Zone {
+    fn default() -> Self {
+        Zone::Named(Tz::UTC)
+    }
+}
+
+impl Zone {
+    /// Picks the zone to use: `--tz` first, then the `TZ` environment
+    /// variable, then the zone saved in the store, falling back to UTC.
+    /// An unparseable `TZ` is ignored since it is shared with other tools.
+    pub fn resolve(flag: Option<&str>, stored: Option<Zone>) -> Result<Zone> {
+        if let Some(tz) = flag {
+            return tz.parse();
+        }
+        if let Some(env) = std::env::var("TZ").ok().and_then(|tz| tz.parse().ok()) {
+            return Ok(env);
+        }
+        Ok(stored.unwrap_or_default())
+    }
+
+    /// The local calendar day of an instant.
+    pub fn date_of(&self, at: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Zone::Named(tz) => at.with_timezone(tz).date_naive(),
+            Zone::Fixed(offset) => at.with_timezone(offset).date_naive(),
+        }
+    }
+
+    /// The local wall-clock time of an instant.
+    pub fn time_of(&self, at: DateTime<Utc>) -> NaiveTime {
+        match self {
+            Zone::Named(tz) => at.with_timezone(tz).time(),
+            Zone::Fixed(offset) => at.with_timezone(offset).time(),
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date_of(Utc::now())
+    }
+
+    /// Local midday of `day` as a UTC instant, used for backdated completions.
+    pub fn midday(&self, day: NaiveDate) -> DateTime<Utc> {
+        let local = day.and_time(NaiveTime::from_hms_opt(12, 0, 0).unwrap_or(NaiveTime::MIN));
+        let at = match self {
+            Zone::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|d| d.to_utc()),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|d| d.to_utc()),
+        };
+        at.unwrap_or_else(|| local.and_utc())
+    }
+
+    pub fn format(&self, at: DateTime<Utc>, fmt: &str) -> String {
+        match self {
+            Zone::Named(tz) => at.with_timezone(tz).format(fmt).to_string(),
+            Zone::Fixed(offset) => at.with_timezone(offset).format(fmt).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+            Zone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+impl FromStr for Zone {
+    type Err = HabitError;
+
+    /// Accepts IANA names (`Asia/Kolkata`), `UTC`, and fixed offsets such as
+    /// `+05:30`, `-0800` or `UTC+5:30`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || HabitError::InvalidTimezone(s.to_string());
+        let input = s.trim().trim_start_matches(':');
+        let upper = input.to_ascii_uppercase();
+        if upper == "UTC" || upper == "Z" || upper == "GMT" {
+            return Ok(Zone::Named(Tz::UTC));
+        }
+        let offset = upper
+            .strip_prefix("UTC")
+            .or_else(|| upper.strip_prefix("GMT"))
+            .unwrap_or(&upper);
+        if let Some(sign) = offset.chars().next().filter(|c| *c == '+' || *c == '-') {
+            let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+            let (hours, minutes) = match digits.len() {
+                1 | 2 => (digits.as_str(), "0"),
+                3 | 4 => digits.split_at(digits.len() - 2),
+                _ => return Err(invalid()),
+            };
+            let hours: i32 = hours.parse().map_err(|_| invalid())?;
+            let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+            if minutes >= 60 {
+                return Err(invalid());
+            }
+            let secs = (hours * 3600 + minutes * 60) * if sign == '-' { -1 } else { 1 };
+            return FixedOffset::east_opt(secs)
+                .map(Zone::Fixed)
+                .ok_or_else(invalid);
+        }
+        input.parse::<Tz>().map(Zone::Named).map_err(|_| invalid())
+    }
+}
+
+impl TryFrom<String> for Zone {
+    type Error = HabitError;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Zone> for String {
+    fn from(value: Zone) -> Self {
+        value.to_string()
+    }
+}
@@ -0,0 +1,133 @@
+use crate::config::Config;
+use crate::error::{HabitError, Result};
+use crate::storage::json_storage::JsonFileRepository;
+use crate::storage::repository::HabitRepository;
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Json,
+    Sqlite,
+}
+
+impl Backend {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Backend::Json => "json",
+            Backend::Sqlite => "db",
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Backend::Json),
+            "sqlite" => Ok(Backend::Sqlite),
+            _ => Err(HabitError::BackendUnavailable(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Json => write!(f, "json"),
+            Backend::Sqlite => write!(f, "sqlite"),
+        }
+    }
+}
+
+/// A backend plus the file it lives in, written as a plain path for JSON or
+/// `sqlite://<path>` for SQLite (so `sqlite:///abs/habits.db` is absolute).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageLocation {
+    pub backend: Backend,
+    pub path: PathBuf,
+}
+
+impl StorageLocation {
+    pub fn new(backend: Backend, path: impl Into<PathBuf>) -> Self {
+        Self {
+            backend,
+            path: path.into(),
+        }
+    }
+
+    /// `HABIT_STORAGE`, then the `storage` config key, then `habits.json`.
+    pub fn resolve(config: &Config) -> Result<Self> {
+        match env::var("HABIT_STORAGE")
+            .ok()
+            .or_else(|| config.storage.clone())
+        {
+            Some(spec) => spec.parse(),
+            None => Ok(Self::new(Backend::Json, "habits.json")),
+        }
+    }
+
+    pub fn open(&self) -> Result<Box<dyn HabitRepository>> {
+        self.open_with(&Config::default())
+    }
+
+    /// Opens the repository, applying tool settings such as the lock timeout.
+    pub fn open_with(&self, config: &Config) -> Result<Box<dyn HabitRepository>> {
+        match self.backend {
+            Backend::Json => Ok(Box::new(
+                JsonFileRepository::new(&self.path)
+                    .with_lock_timeout(config.lock_timeout())
+                    .with_backup_policy(config.backups),
+            )),
+            #[cfg(feature = "sqlite")]
+            Backend::Sqlite => Ok(Box::new(
+                crate::storage::sqlite_storage::SqliteRepository::open(&self.path)?
+                    .with_lock_timeout(config.lock_timeout())
+                    .with_backup_policy(config.backups),
+            )),
+            #[cfg(not(feature = "sqlite"))]
+            Backend::Sqlite => Err(HabitError::BackendUnavailable(
+                "sqlite (rebuild with --features sqlite)".into(),
+            )),
+        }
+    }
+
+    /// The same location with another backend, swapping the file extension.
+    pub fn with_backend(&self, backend: Backend) -> Self {
+        Self::new(backend, self.path.with_extension(backend.extension()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl FromStr for StorageLocation {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(path) = s.strip_prefix("sqlite://") {
+            return Ok(Self::new(Backend::Sqlite, path));
+        }
+        let path = s.strip_prefix("json://").unwrap_or(s);
+        Ok(Self::new(Backend::Json, path))
+    }
+}
+
+impl fmt::Display for StorageLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.backend {
+            Backend::Json => write!(f, "{}", self.path.display()),
+            Backend::Sqlite => write!(f, "sqlite://{}", self.path.display()),
+        }
+    }
+}
+
+/// Opens the repository selected by the environment and config file.
+pub fn open_repository() -> Result<Box<dyn HabitRepository>> {
+    let config = Config::load()?;
+    StorageLocation::resolve(&config)?.open_with(&config)
+}
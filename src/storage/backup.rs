@@ -0,0 +1,172 @@
+use crate::error::{HabitError, Result};
+use crate::storage::json_storage::HabitStore;
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// How many backups survive pruning: the newest `keep`, plus the newest
+/// backup of each of the last `daily` days and `weekly` ISO weeks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupPolicy {
+    pub keep: usize,
+    pub daily: usize,
+    pub weekly: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            keep: 10,
+            daily: 7,
+            weekly: 4,
+        }
+    }
+}
+
+impl BackupPolicy {
+    pub fn is_disabled(&self) -> bool {
+        self.keep == 0 && self.daily == 0 && self.weekly == 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BackupEntry {
+    pub taken_at: DateTime<Utc>,
+    pub path: PathBuf,
+}
+
+impl BackupEntry {
+    /// The identifier accepted by `habit backup restore`.
+    pub fn id(&self) -> String {
+        self.taken_at.format("%Y%m%dT%H%M%S%.3f").to_string()
+    }
+
+    pub fn load(&self) -> Result<HabitStore> {
+        HabitStore::from_slice(&fs::read(&self.path)?)
+    }
+}
+
+/// Timestamped JSON snapshots of a store, kept in `<store>.backups/`.
+#[derive(Debug, Clone)]
+pub struct Backups {
+    dir: PathBuf,
+}
+
+impl Backups {
+    pub fn for_store(store: &Path) -> Self {
+        let mut name = store.file_name().unwrap_or_default().to_os_string();
+        name.push(".backups");
+        Self {
+            dir: store.with_file_name(name),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Snapshots `store` as a new backup.
+    pub fn create(&self, store: &HabitStore) -> Result<BackupEntry> {
+        self.write(&serde_json::to_vec_pretty(store)?)
+    }
+
+    /// Copies an existing JSON store file as a new backup.
+    pub fn create_from_file(&self, file: &Path) -> Result<BackupEntry> {
+        self.write(&fs::read(file)?)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<BackupEntry> {
+        fs::create_dir_all(&self.dir)?;
+        let mut taken_at = Utc::now();
+        // two saves within the same millisecond must not overwrite each other
+        while self.path_for(taken_at).exists() {
+            taken_at += chrono::Duration::milliseconds(1);
+        }
+        let path = self.path_for(taken_at);
+        fs::write(&path, data)?;
+        Ok(BackupEntry { taken_at, path })
+    }
+
+    fn path_for(&self, taken_at: DateTime<Utc>) -> PathBuf {
+        self.dir
+            .join(format!("habits-{}.json", taken_at.format(STAMP_FORMAT)))
+    }
+
+    /// All backups, newest first.
+    pub fn list(&self) -> Result<Vec<BackupEntry>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let stamp = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("habits-"))
+                .and_then(|n| n.strip_suffix(".json"));
+            if let Some(Ok(taken)) = stamp.map(|s| NaiveDateTime::parse_from_str(s, STAMP_FORMAT)) {
+                entries.push(BackupEntry {
+                    taken_at: taken.and_utc(),
+                    path,
+                });
+            }
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.taken_at));
+        Ok(entries)
+    }
+
+    /// Finds a backup by its number in [`Backups::list`] (1 is the newest)
+    /// or by a prefix of its timestamp id.
+    pub fn find(&self, id: &str) -> Result<BackupEntry> {
+        let entries = self.list()?;
+        if let Ok(n) = id.parse::<usize>()
+            && let Some(entry) = n.checked_sub(1).and_then(|i| entries.get(i))
+        {
+            return Ok(entry.clone());
+        }
+        let id = id.replace(['-', ':'], "");
+        let mut matches = entries.into_iter().filter(|e| e.id().starts_with(&id));
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(entry),
+            _ => Err(HabitError::BackupNotFound(id)),
+        }
+    }
+
+    /// Deletes backups outside `policy` and returns how many were removed.
+    pub fn prune(&self, policy: &BackupPolicy) -> Result<usize> {
+        let entries = self.list()?;
+        let mut keep: HashSet<&Path> = entries
+            .iter()
+            .take(policy.keep)
+            .map(|e| e.path.as_path())
+            .collect();
+        let mut days = Vec::new();
+        let mut weeks = Vec::new();
+        for e in &entries {
+            let day = e.taken_at.date_naive();
+            if !days.contains(&day) && days.len() < policy.daily {
+                days.push(day);
+                keep.insert(&e.path);
+            }
+            let week = day.iso_week();
+            if !weeks.contains(&week) && weeks.len() < policy.weekly {
+                weeks.push(week);
+                keep.insert(&e.path);
+            }
+        }
+        let mut removed = 0;
+        for e in &entries {
+            if !keep.contains(e.path.as_path()) {
+                fs::remove_file(&e.path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
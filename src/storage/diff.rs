@@ -0,0 +1,107 @@
+use crate::models::habit::Habit;
+use crate::storage::json_storage::HabitStore;
+
+/// How one habit differs between two stores.
+#[derive(Debug, Clone, Copy)]
+pub enum HabitChange<'a> {
+    Added(&'a Habit),
+    Removed(&'a Habit),
+    Modified { before: &'a Habit, after: &'a Habit },
+}
+
+impl HabitChange<'_> {
+    /// A one-line human summary, e.g. `~ Read: +2 completions, schedule daily → 3/week`.
+    pub fn describe(&self) -> String {
+        match self {
+            HabitChange::Added(h) => {
+                format!("+ {} ({} completions)", h.name, h.completions.len())
+            }
+            HabitChange::Removed(h) => {
+                format!("- {} ({} completions)", h.name, h.completions.len())
+            }
+            HabitChange::Modified { before, after } => {
+                let mut parts = Vec::new();
+                if before.name != after.name {
+                    parts.push(format!("renamed '{}' → '{}'", before.name, after.name));
+                }
+                let added = after
+                    .completions
+                    .iter()
+                    .filter(|c| !before.completions.contains(c))
+                    .count();
+                let removed = before
+                    .completions
+                    .iter()
+                    .filter(|c| !after.completions.contains(c))
+                    .count();
+                if added > 0 {
+                    parts.push(format!("+{} completions", added));
+                }
+                if removed > 0 {
+                    parts.push(format!("-{} completions", removed));
+                }
+                if before.schedule != after.schedule {
+                    parts.push(format!("schedule {} → {}", before.schedule, after.schedule));
+                }
+                if before.description != after.description {
+                    parts.push("description changed".into());
+                }
+                if before.created_at != after.created_at {
+                    parts.push("creation date changed".into());
+                }
+                if before.goal != after.goal {
+                    parts.push("target changed".into());
+                }
+                if before.tags != after.tags {
+                    parts.push("tags changed".into());
+                }
+                if before.is_active != after.is_active {
+                    parts.push(
+                        if after.is_active {
+                            "activated"
+                        } else {
+                            "deactivated"
+                        }
+                        .into(),
+                    );
+                }
+                if before.pauses != after.pauses {
+                    parts.push("pauses changed".into());
+                }
+                if parts.is_empty() {
+                    parts.push("other details changed".into());
+                }
+                format!("~ {}: {}", after.name, parts.join(", "))
+            }
+        }
+    }
+}
+
+/// Every habit that differs from `from` to `to`, matched by id. Should a
+/// hand-edited store repeat an id, its habits pair up in order.
+pub fn diff<'a>(from: &'a HabitStore, to: &'a HabitStore) -> Vec<HabitChange<'a>> {
+    let mut changes = Vec::new();
+    let mut matched = vec![false; to.habits.len()];
+    for before in &from.habits {
+        let counterpart = to
+            .habits
+            .iter()
+            .enumerate()
+            .find(|(i, h)| !matched[*i] && h.id == before.id);
+        match counterpart {
+            None => changes.push(HabitChange::Removed(before)),
+            Some((i, after)) => {
+                matched[i] = true;
+                if after != before {
+                    changes.push(HabitChange::Modified { before, after });
+                }
+            }
+        }
+    }
+    for (after, matched) in to.habits.iter().zip(matched) {
+        if !matched {
+            changes.push(HabitChange::Added(after));
+        }
+    }
+    changes
+}
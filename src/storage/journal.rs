@@ -0,0 +1,410 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::{Completion, Habit};
+use crate::storage::diff::{self, HabitChange};
+use crate::storage::json_storage::{HabitStore, Settings};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// One change to the store. Applying the events of every journal entry in
+/// order to an empty store rebuilds the current one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    HabitAdded {
+        index: usize,
+        habit: Habit,
+    },
+    HabitRemoved {
+        index: usize,
+        habit: Habit,
+    },
+    Completed {
+        habit: Uuid,
+        name: String,
+        completions: Vec<Completion>,
+    },
+    Uncompleted {
+        habit: Uuid,
+        name: String,
+        completions: Vec<Completion>,
+    },
+    Edited {
+        before: Box<Habit>,
+        after: Box<Habit>,
+    },
+    SettingsChanged {
+        before: Settings,
+        after: Settings,
+    },
+}
+
+impl Event {
+    /// The event that reverts this one.
+    pub fn inverse(&self) -> Event {
+        match self.clone() {
+            Event::HabitAdded { index, habit } => Event::HabitRemoved { index, habit },
+            Event::HabitRemoved { index, habit } => Event::HabitAdded { index, habit },
+            Event::Completed {
+                habit,
+                name,
+                completions,
+            } => Event::Uncompleted {
+                habit,
+                name,
+                completions,
+            },
+            Event::Uncompleted {
+                habit,
+                name,
+                completions,
+            } => Event::Completed {
+                habit,
+                name,
+                completions,
+            },
+            Event::Edited { before, after } => Event::Edited {
+                before: after,
+                after: before,
+            },
+            Event::SettingsChanged { before, after } => Event::SettingsChanged {
+                before: after,
+                after: before,
+            },
+        }
+    }
+
+    pub fn apply(&self, store: &mut HabitStore) -> Result<()> {
+        match self {
+            Event::HabitAdded { index, habit } => {
+                let index = (*index).min(store.habits.len());
+                store.habits.insert(index, habit.clone());
+            }
+            Event::HabitRemoved { index, habit } => {
+                // the recorded index tells apart habits sharing an id
+                let index = match store.habits.get(*index) {
+                    Some(h) if h.id == habit.id => *index,
+                    _ => position(store, habit.id, &habit.name)?,
+                };
+                store.habits.remove(index);
+            }
+            Event::Completed {
+                habit,
+                name,
+                completions,
+            } => {
+                let index = position(store, *habit, name)?;
+                let habit = &mut store.habits[index];
+                habit.completions.extend(completions.iter().cloned());
+                habit.completions.sort_by_key(|c| c.at);
+            }
+            Event::Uncompleted {
+                habit,
+                name,
+                completions,
+            } => {
+                let index = position(store, *habit, name)?;
+                store.habits[index]
+                    .completions
+                    .retain(|c| !completions.contains(c));
+            }
+            Event::Edited { before, after } => {
+                let index = position(store, before.id, &before.name)?;
+                store.habits[index] = (**after).clone();
+            }
+            Event::SettingsChanged { after, .. } => store.settings = after.clone(),
+        }
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Event::HabitAdded { habit, .. } => format!("+ added '{}'", habit.name),
+            Event::HabitRemoved { habit, .. } => format!(
+                "- removed '{}' ({} completions)",
+                habit.name,
+                habit.completions.len()
+            ),
+            Event::Completed {
+                name, completions, ..
+            } => format!("✓ '{}' +{} completions", name, completions.len()),
+            Event::Uncompleted {
+                name, completions, ..
+            } => format!("✗ '{}' -{} completions", name, completions.len()),
+            Event::Edited { before, after } => HabitChange::Modified { before, after }.describe(),
+            Event::SettingsChanged { .. } => "~ settings changed".into(),
+        }
+    }
+}
+
+fn position(store: &HabitStore, id: Uuid, name: &str) -> Result<usize> {
+    store
+        .habits
+        .iter()
+        .position(|h| h.id == id)
+        .ok_or_else(|| conflict(name, "no longer exists"))
+}
+
+fn conflict(name: &str, problem: &str) -> HabitError {
+    HabitError::JournalConflict(format!("habit '{}' {}", name, problem))
+}
+
+/// The events that turn `from` into `to`.
+pub fn events_between(from: &HabitStore, to: &HabitStore) -> Vec<Event> {
+    let mut events = Vec::new();
+    if from.settings != to.settings {
+        events.push(Event::SettingsChanged {
+            before: from.settings.clone(),
+            after: to.settings.clone(),
+        });
+    }
+    let index_in = |store: &HabitStore, habit: &Habit| {
+        store
+            .habits
+            .iter()
+            .position(|h| std::ptr::eq(h, habit))
+            .unwrap_or(0)
+    };
+    for change in diff::diff(from, to) {
+        match change {
+            HabitChange::Removed(habit) => events.push(Event::HabitRemoved {
+                index: index_in(from, habit),
+                habit: habit.clone(),
+            }),
+            HabitChange::Added(habit) => events.push(Event::HabitAdded {
+                index: index_in(to, habit),
+                habit: habit.clone(),
+            }),
+            HabitChange::Modified { before, after } => {
+                let same_details = Habit {
+                    completions: Vec::new(),
+                    ..before.clone()
+                } == Habit {
+                    completions: Vec::new(),
+                    ..after.clone()
+                };
+                if !same_details {
+                    events.push(Event::Edited {
+                        before: Box::new(before.clone()),
+                        after: Box::new(after.clone()),
+                    });
+                    continue;
+                }
+                let removed: Vec<_> = before
+                    .completions
+                    .iter()
+                    .filter(|c| !after.completions.contains(c))
+                    .cloned()
+                    .collect();
+                let added: Vec<_> = after
+                    .completions
+                    .iter()
+                    .filter(|c| !before.completions.contains(c))
+                    .cloned()
+                    .collect();
+                if !removed.is_empty() {
+                    events.push(Event::Uncompleted {
+                        habit: after.id,
+                        name: after.name.clone(),
+                        completions: removed,
+                    });
+                }
+                if !added.is_empty() {
+                    events.push(Event::Completed {
+                        habit: after.id,
+                        name: after.name.clone(),
+                        completions: added,
+                    });
+                }
+            }
+        }
+    }
+    events
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EntryKind {
+    /// Habits that already existed when the journal was started.
+    Baseline,
+    Command,
+    Undo {
+        of: u64,
+    },
+    Redo {
+        of: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    pub command: String,
+    #[serde(flatten)]
+    pub kind: EntryKind,
+    pub events: Vec<Event>,
+}
+
+/// Which command entries are currently applied (`done`) and which were
+/// undone and can be redone, both with the most recent last.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct History {
+    pub done: Vec<u64>,
+    pub undone: Vec<u64>,
+}
+
+impl History {
+    pub fn of(entries: &[JournalEntry]) -> Self {
+        let mut history = Self::default();
+        for entry in entries {
+            match entry.kind {
+                EntryKind::Baseline => {}
+                EntryKind::Command => {
+                    history.done.push(entry.seq);
+                    history.undone.clear();
+                }
+                EntryKind::Undo { of } => {
+                    history.done.retain(|&s| s != of);
+                    history.undone.push(of);
+                }
+                EntryKind::Redo { of } => {
+                    history.undone.retain(|&s| s != of);
+                    history.done.push(of);
+                }
+            }
+        }
+        history
+    }
+}
+
+/// Append-only log of every change, one JSON entry per line in
+/// `<store>.journal`.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn for_store(store: &Path) -> Self {
+        let mut name = store.file_name().unwrap_or_default().to_os_string();
+        name.push(".journal");
+        Self {
+            path: store.with_file_name(name),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let data = fs::read_to_string(&self.path)?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    pub fn append(
+        &self,
+        command: &str,
+        kind: EntryKind,
+        events: Vec<Event>,
+    ) -> Result<JournalEntry> {
+        let seq = self.last_seq()? + 1;
+        let entry = JournalEntry {
+            seq,
+            at: Utc::now(),
+            command: command.to_string(),
+            kind,
+            events,
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        file.sync_data()?;
+        Ok(entry)
+    }
+
+    /// The `seq` of the last entry, or 0 for an empty journal. Only the end
+    /// of the file is read, so appending stays cheap as the journal grows.
+    fn last_seq(&self) -> Result<u64> {
+        #[derive(Deserialize)]
+        struct Seq {
+            seq: u64,
+        }
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+        let mut start = file.metadata()?.len();
+        let mut tail = Vec::new();
+        loop {
+            let step = start.min(4096);
+            start -= step;
+            let mut block = vec![0; step as usize];
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut block)?;
+            block.extend_from_slice(&tail);
+            tail = block;
+            let text = tail.trim_ascii_end();
+            let line = match text.iter().rposition(|&b| b == b'\n') {
+                Some(newline) => &text[newline + 1..],
+                None if start == 0 => text,
+                None => continue,
+            };
+            if line.is_empty() {
+                return Ok(0);
+            }
+            return Ok(serde_json::from_slice::<Seq>(line)?.seq);
+        }
+    }
+
+    /// Journals the change a command made from `before` to `after`. The
+    /// first entry of a journal started on a non-empty store is a baseline
+    /// holding everything that existed beforehand.
+    pub fn record(
+        &self,
+        command: &str,
+        before: &HabitStore,
+        after: &HabitStore,
+    ) -> Result<Option<JournalEntry>> {
+        if before == after {
+            return Ok(None);
+        }
+        let events = events_between(before, after);
+        if events.is_empty() {
+            return Ok(None);
+        }
+        if !self.path.exists() {
+            let baseline = events_between(&HabitStore::default(), before);
+            if !baseline.is_empty() {
+                self.append("(existing store)", EntryKind::Baseline, baseline)?;
+            }
+        }
+        self.append(command, EntryKind::Command, events).map(Some)
+    }
+
+    /// Rebuilds the store by applying every entry to an empty one.
+    pub fn replay(&self) -> Result<HabitStore> {
+        let mut store = HabitStore::default();
+        for entry in self.entries()? {
+            for event in &entry.events {
+                event.apply(&mut store)?;
+            }
+        }
+        Ok(store)
+    }
+}
@@ -1,53 +1,204 @@
-use crate::error::Result;
-use crate::models::habit::Habit;
+use crate::error::{HabitError, Result};
+use crate::models::habit::{Habit, Pause};
+use crate::models::timezone::Zone;
+use crate::storage::backend::Backend;
+use crate::storage::backup::{BackupPolicy, Backups};
+use crate::storage::lock::{DEFAULT_LOCK_TIMEOUT, StoreLock};
+use crate::storage::migrations::{self, CURRENT_SCHEMA_VERSION};
+use crate::storage::repository::HabitRepository;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs::{self, File};
-use std::io::{BufReader, Write};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use uuid::Uuid;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Timezone used for day boundaries unless overridden by `TZ` or `--tz`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Zone>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HabitStore {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub settings: Settings,
     pub habits: Vec<Habit>,
 }
 
+impl Default for HabitStore {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            settings: Settings::default(),
+            habits: Vec::new(),
+        }
+    }
+}
+
 impl HabitStore {
-    pub fn load() -> Result<Self> {
-        let path = storage_path();
+    /// Loads a store, upgrading files written by older versions. The
+    /// original is copied to `<file>.v<N>.<timestamp>.bak` and the upgraded
+    /// store is written back before returning.
+    pub fn load_from(path: &Path) -> Result<Self> {
         if !path.exists() {
-            return Ok(Self { habits: Vec::new() });
+            return Ok(Self::default());
+        }
+        let data = fs::read(path)?;
+        let corrupt =
+            |err: serde_json::Error| HabitError::Corrupt(format!("{}: {}", path.display(), err));
+        let from = migrations::version_of(&serde_json::from_slice(&data).map_err(corrupt)?)?;
+        let store = match Self::from_slice(&data) {
+            Err(HabitError::Serde(err)) => return Err(corrupt(err)),
+            other => other?,
+        };
+        if from != CURRENT_SCHEMA_VERSION {
+            let mut backup = path.as_os_str().to_os_string();
+            backup.push(format!(
+                ".v{}.{}.bak",
+                from,
+                Utc::now().format("%Y%m%dT%H%M%S")
+            ));
+            fs::write(&backup, &data)?;
+            store.save_to(path)?;
         }
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let store: Self = serde_json::from_reader(reader)?;
         Ok(store)
     }
 
-    pub fn save(&self) -> Result<()> {
-        let path = storage_path();
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        let tmp = path.with_extension("json.tmp");
+    /// Parses a serialized store of any supported version, migrating it in
+    /// memory only.
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_slice(data)?;
+        migrations::migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Writes the store durably: the data goes to a temp file that is
+    /// fsynced and renamed over the store, then the directory is fsynced so
+    /// the rename itself survives a crash. The store's permissions are kept.
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        fs::create_dir_all(dir)?;
+        let tmp = temp_path(path);
         let mut f = File::create(&tmp)?;
+        if let Ok(meta) = fs::metadata(path) {
+            f.set_permissions(meta.permissions())?;
+        }
         let data = serde_json::to_vec_pretty(self)?;
         f.write_all(&data)?;
-        f.flush()?;
+        f.sync_all()?;
+        drop(f);
         fs::rename(tmp, path)?;
+        #[cfg(unix)]
+        File::open(dir)?.sync_all()?;
         Ok(())
     }
 
+    /// Vacations any habit is paused for, earliest first.
+    pub fn vacations(&self) -> Vec<Pause> {
+        let mut vacations: Vec<Pause> = self
+            .habits
+            .iter()
+            .flat_map(|h| h.pauses.iter().filter(|p| p.vacation).copied())
+            .collect();
+        vacations.sort_by_key(|v| (v.from, v.until));
+        vacations.dedup();
+        vacations
+    }
+
+    pub fn find_by_ident(&self, ident: &str) -> Option<&Habit> {
+        if let Ok(id) = ident.parse::<Uuid>() {
+            self.habits.iter().find(|h| h.id == id)
+        } else {
+            self.habits.iter().find(|h| h.has_name(ident))
+        }
+    }
+
     pub fn find_by_ident_mut(&mut self, ident: &str) -> Option<&mut Habit> {
         // match by UUID or name
         if let Ok(id) = ident.parse::<Uuid>() {
             self.habits.iter_mut().find(|h| h.id == id)
         } else {
-            self.habits
-                .iter_mut()
-                .find(|h| h.name.eq_ignore_ascii_case(ident))
+            self.habits.iter_mut().find(|h| h.has_name(ident))
+        }
+    }
+}
+
+/// Stores every habit in a single JSON file.
+#[derive(Debug, Clone)]
+pub struct JsonFileRepository {
+    path: PathBuf,
+    lock_timeout: Duration,
+    backup_policy: BackupPolicy,
+}
+
+impl JsonFileRepository {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            backup_policy: BackupPolicy::default(),
         }
     }
+
+    pub fn with_backup_policy(mut self, policy: BackupPolicy) -> Self {
+        self.backup_policy = policy;
+        self
+    }
+
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// `HABIT_STORAGE` if set, otherwise `habits.json` in the working directory.
+    pub fn from_env() -> Self {
+        Self::new(storage_path())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl HabitRepository for JsonFileRepository {
+    fn load(&self) -> Result<HabitStore> {
+        HabitStore::load_from(&self.path)
+    }
+
+    /// Backs up the file being replaced before writing the new store.
+    fn save(&mut self, store: &HabitStore) -> Result<()> {
+        if !self.backup_policy.is_disabled() && self.path.exists() {
+            let backups = Backups::for_store(&self.path);
+            backups.create_from_file(&self.path)?;
+            backups.prune(&self.backup_policy)?;
+        }
+        store.save_to(&self.path)
+    }
+
+    fn location(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        Some(Backend::Json)
+    }
+
+    fn lock(&self) -> Result<Option<StoreLock>> {
+        StoreLock::acquire(&self.path, self.lock_timeout).map(Some)
+    }
+}
+
+/// Where [`HabitStore::save_to`] stages a save before renaming it into place.
+pub fn temp_path(store: &Path) -> PathBuf {
+    store.with_extension("json.tmp")
 }
 
 fn storage_path() -> PathBuf {
@@ -0,0 +1,68 @@
+use crate::error::{HabitError, Result};
+use crate::models::schedule::Schedule;
+use serde_json::{Map, Value};
+
+/// Version written by this build.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+type Step = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// `STEPS[i]` upgrades a store from version `i + 1` to `i + 2`.
+const STEPS: &[Step] = &[v1_to_v2];
+
+/// The schema version of a raw store. Files written before versioning have
+/// no `schema_version` field and are version 1.
+pub fn version_of(value: &Value) -> Result<u32> {
+    match value.get("schema_version") {
+        None => Ok(1),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| HabitError::UnsupportedVersion(v.to_string())),
+    }
+}
+
+/// Upgrades `value` in place to [`CURRENT_SCHEMA_VERSION`], one step at a
+/// time. Returns the version it started from.
+pub fn migrate(value: &mut Value) -> Result<u32> {
+    let from = version_of(value)?;
+    if from > CURRENT_SCHEMA_VERSION || from == 0 {
+        return Err(HabitError::UnsupportedVersion(format!(
+            "{} (this build reads up to {})",
+            from, CURRENT_SCHEMA_VERSION
+        )));
+    }
+    let root = value
+        .as_object_mut()
+        .ok_or_else(|| HabitError::UnsupportedVersion("store is not a JSON object".into()))?;
+    for (i, step) in STEPS.iter().enumerate().skip(from as usize - 1) {
+        step(root)?;
+        root.insert("schema_version".into(), Value::from(i as u32 + 2));
+    }
+    Ok(from)
+}
+
+/// v1 stored `target_frequency` (days per week) and nothing but habits;
+/// v2 has a `schedule` string per habit and a `settings` block.
+fn v1_to_v2(root: &mut Map<String, Value>) -> Result<()> {
+    root.entry("settings")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let habits = root
+        .get_mut("habits")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten();
+    for habit in habits.filter_map(Value::as_object_mut) {
+        let frequency = habit.remove("target_frequency");
+        if habit.contains_key("schedule") {
+            continue;
+        }
+        let schedule = frequency
+            .as_ref()
+            .and_then(Value::as_u64)
+            .map(|n| Schedule::from_weekly_target(n.min(u32::MAX as u64) as u32))
+            .unwrap_or_default();
+        habit.insert("schedule".into(), Value::from(schedule.to_string()));
+    }
+    Ok(())
+}
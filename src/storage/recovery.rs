@@ -0,0 +1,202 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::Habit;
+use crate::storage::backup::Backups;
+use crate::storage::journal::Journal;
+use crate::storage::json_storage::{self, HabitStore};
+use crate::storage::migrations;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoverySource {
+    /// A save that was fully written but never renamed into place.
+    TempFile,
+    Journal,
+    Backup,
+    /// Whatever habits still parse in the damaged file.
+    Salvage,
+}
+
+impl FromStr for RecoverySource {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "temp" | "tmp" => Ok(RecoverySource::TempFile),
+            "journal" => Ok(RecoverySource::Journal),
+            "backup" => Ok(RecoverySource::Backup),
+            "salvage" => Ok(RecoverySource::Salvage),
+            _ => Err(HabitError::BackendUnavailable(format!(
+                "unknown recovery source '{}' (temp, journal, backup, salvage)",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for RecoverySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecoverySource::TempFile => write!(f, "temp"),
+            RecoverySource::Journal => write!(f, "journal"),
+            RecoverySource::Backup => write!(f, "backup"),
+            RecoverySource::Salvage => write!(f, "salvage"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub source: RecoverySource,
+    pub detail: String,
+    pub store: HabitStore,
+}
+
+/// The habits that could still be read from a damaged store file, and a note
+/// for each part that could not.
+#[derive(Debug, Clone, Default)]
+pub struct Salvage {
+    pub store: HabitStore,
+    pub lost: Vec<String>,
+}
+
+/// Every usable replacement for the damaged store at `path`, best first:
+/// an unrenamed temp file, a replay of the journal, the newest readable
+/// backup, and finally what can be salvaged from the file itself. Only a
+/// JSON file (`salvageable`) can be salvaged.
+pub fn candidates(path: &Path, salvageable: bool) -> (Vec<Candidate>, Salvage) {
+    let mut found = Vec::new();
+    let tmp = json_storage::temp_path(path);
+    if let Ok(store) = fs::read(&tmp)
+        .map_err(HabitError::from)
+        .and_then(|data| HabitStore::from_slice(&data))
+    {
+        found.push(Candidate {
+            source: RecoverySource::TempFile,
+            detail: tmp.display().to_string(),
+            store,
+        });
+    }
+    let journal = Journal::for_store(path);
+    if journal.path().exists()
+        && let Ok(store) = journal.replay()
+    {
+        found.push(Candidate {
+            source: RecoverySource::Journal,
+            detail: journal.path().display().to_string(),
+            store,
+        });
+    }
+    let backups = Backups::for_store(path).list().unwrap_or_default();
+    if let Some((entry, store)) = backups
+        .iter()
+        .find_map(|entry| entry.load().ok().map(|store| (entry, store)))
+    {
+        found.push(Candidate {
+            source: RecoverySource::Backup,
+            detail: format!("backup {}", entry.id()),
+            store,
+        });
+    }
+    if !salvageable {
+        return (found, Salvage::default());
+    }
+    let salvaged = salvage(&fs::read(path).unwrap_or_default());
+    found.push(Candidate {
+        source: RecoverySource::Salvage,
+        detail: format!(
+            "{} habits read from the damaged file",
+            salvaged.store.habits.len()
+        ),
+        store: salvaged.store.clone(),
+    });
+    (found, salvaged)
+}
+
+/// Reads every habit that still parses on its own, even from a truncated file.
+pub fn salvage(data: &[u8]) -> Salvage {
+    let mut salvaged = Salvage::default();
+    let items = match serde_json::from_slice::<Value>(data) {
+        Ok(mut value) => {
+            if migrations::migrate(&mut value).is_err() {
+                salvaged
+                    .lost
+                    .push("schema upgrade of the damaged file failed".into());
+            }
+            match value.get_mut("settings").map(Value::take) {
+                None | Some(Value::Null) => {}
+                Some(settings) => match serde_json::from_value(settings) {
+                    Ok(settings) => salvaged.store.settings = settings,
+                    Err(_) => salvaged.lost.push("settings".into()),
+                },
+            }
+            match value.get_mut("habits").map(Value::take) {
+                Some(Value::Array(items)) => items,
+                _ => {
+                    salvaged.lost.push("the habit list".into());
+                    Vec::new()
+                }
+            }
+        }
+        Err(_) => {
+            salvaged.lost.push("settings".into());
+            scan_habits(data, &mut salvaged.lost)
+        }
+    };
+    for item in items {
+        match serde_json::from_value::<Habit>(item.clone()) {
+            Ok(habit) => salvaged.store.habits.push(habit),
+            Err(err) => salvaged.lost.push(format!(
+                "habit '{}' ({})",
+                item["name"].as_str().unwrap_or("unnamed"),
+                err
+            )),
+        }
+    }
+    salvaged
+}
+
+/// Collects the complete JSON values in the `habits` array of a file that no
+/// longer parses as a whole, stopping at the first damaged one.
+fn scan_habits(data: &[u8], lost: &mut Vec<String>) -> Vec<Value> {
+    let key = b"\"habits\"";
+    let Some(start) = data.windows(key.len()).position(|w| w == key) else {
+        lost.push("the habit list".into());
+        return Vec::new();
+    };
+    let Some(open) = data[start..].iter().position(|&b| b == b'[') else {
+        lost.push("the habit list".into());
+        return Vec::new();
+    };
+    let mut rest = &data[start + open + 1..];
+    let mut items = Vec::new();
+    loop {
+        let skip = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace() && *b != b',')
+            .unwrap_or(rest.len());
+        rest = &rest[skip..];
+        if rest.first().is_none_or(|&b| b == b']') {
+            break;
+        }
+        let mut stream = serde_json::Deserializer::from_slice(rest).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) => {
+                rest = &rest[stream.byte_offset()..];
+                items.push(value);
+            }
+            _ => {
+                lost.push(format!(
+                    "everything after habit #{} ({} bytes)",
+                    items.len(),
+                    rest.len()
+                ));
+                break;
+            }
+        }
+    }
+    items
+}
@@ -0,0 +1,130 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::Habit;
+use crate::storage::backend::Backend;
+use crate::storage::json_storage::HabitStore;
+use crate::storage::lock::StoreLock;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Where habits are persisted.
+///
+/// Implementations only need `load` and `save`; the per-habit operations
+/// default to a load–modify–save cycle, which backends with finer-grained
+/// storage can override. Writes run under the lock returned by
+/// [`HabitRepository::lock`]: the one the caller passes in, or a fresh one
+/// taken for the write when it passes `None`.
+pub trait HabitRepository {
+    fn load(&self) -> Result<HabitStore>;
+
+    fn save(&mut self, store: &HabitStore) -> Result<()>;
+
+    /// Looks a habit up by UUID or case-insensitive name.
+    fn find(&self, ident: &str) -> Result<Option<Habit>> {
+        Ok(self.load()?.find_by_ident(ident).cloned())
+    }
+
+    fn insert(&mut self, lock: Option<&StoreLock>, habit: Habit) -> Result<()> {
+        self.transaction(lock, &mut |store| {
+            if store.habits.iter().any(|h| h.id == habit.id) {
+                return Err(HabitError::AlreadyExists(habit.id.to_string()));
+            }
+            store.habits.push(habit.clone());
+            Ok(())
+        })
+    }
+
+    fn update(&mut self, lock: Option<&StoreLock>, habit: &Habit) -> Result<()> {
+        self.transaction(lock, &mut |store| {
+            let slot = store
+                .habits
+                .iter_mut()
+                .find(|h| h.id == habit.id)
+                .ok_or_else(|| HabitError::NotFound(habit.id.to_string()))?;
+            *slot = habit.clone();
+            Ok(())
+        })
+    }
+
+    fn delete(&mut self, lock: Option<&StoreLock>, id: Uuid) -> Result<Habit> {
+        let mut removed = None;
+        self.transaction(lock, &mut |store| {
+            let idx = store
+                .habits
+                .iter()
+                .position(|h| h.id == id)
+                .ok_or_else(|| HabitError::NotFound(id.to_string()))?;
+            removed = Some(store.habits.remove(idx));
+            Ok(())
+        })?;
+        removed.ok_or_else(|| HabitError::NotFound(id.to_string()))
+    }
+
+    /// The file backing this repository, if any.
+    fn location(&self) -> Option<&Path> {
+        None
+    }
+
+    /// The format of that file.
+    fn backend(&self) -> Option<Backend> {
+        None
+    }
+
+    /// Takes the cross-process lock guarding a load–modify–save cycle.
+    /// Backends that need no locking return `None`.
+    fn lock(&self) -> Result<Option<StoreLock>> {
+        Ok(None)
+    }
+
+    /// Applies `f` to the current store and saves the result, or saves
+    /// nothing if `f` fails. `lock` is the caller's; without one, the cycle
+    /// takes and holds its own.
+    fn transaction(
+        &mut self,
+        lock: Option<&StoreLock>,
+        f: &mut dyn FnMut(&mut HabitStore) -> Result<()>,
+    ) -> Result<()> {
+        let _lock = write_lock(self, lock)?;
+        let mut store = self.load()?;
+        f(&mut store)?;
+        self.save(&store)
+    }
+}
+
+/// The lock a write must take itself: none when the caller already holds
+/// `held`, since taking another would wait on it.
+pub(crate) fn write_lock<R: HabitRepository + ?Sized>(
+    repo: &R,
+    held: Option<&StoreLock>,
+) -> Result<Option<StoreLock>> {
+    match held {
+        Some(_) => Ok(None),
+        None => repo.lock(),
+    }
+}
+
+/// Keeps the store in memory; useful for tests and for embedding.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryRepository {
+    store: HabitStore,
+}
+
+impl MemoryRepository {
+    pub fn new(store: HabitStore) -> Self {
+        Self { store }
+    }
+
+    pub fn store(&self) -> &HabitStore {
+        &self.store
+    }
+}
+
+impl HabitRepository for MemoryRepository {
+    fn load(&self) -> Result<HabitStore> {
+        Ok(self.store.clone())
+    }
+
+    fn save(&mut self, store: &HabitStore) -> Result<()> {
+        self.store = store.clone();
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::{Completion, Goal, Habit, Pause};
+use crate::storage::backend::Backend;
+use crate::storage::backup::{BackupPolicy, Backups};
+use crate::storage::json_storage::{HabitStore, Settings};
+use crate::storage::lock::{DEFAULT_LOCK_TIMEOUT, StoreLock};
+use crate::storage::repository::{HabitRepository, write_lock};
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how
+/// many have run, so only append to this list.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE habits (
+        id          TEXT PRIMARY KEY,
+        position    INTEGER NOT NULL,
+        name        TEXT NOT NULL,
+        description TEXT,
+        created_at  TEXT NOT NULL,
+        schedule    TEXT NOT NULL,
+        goal_target REAL,
+        goal_unit   TEXT,
+        is_active   INTEGER NOT NULL
+    );
+    CREATE TABLE completions (
+        habit_id TEXT NOT NULL REFERENCES habits(id) ON DELETE CASCADE,
+        at       TEXT NOT NULL,
+        amount   REAL
+    );
+    CREATE INDEX completions_habit_at ON completions (habit_id, at);
+    CREATE TABLE settings (
+        key   TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+",
+    "
+    ALTER TABLE habits ADD COLUMN tags TEXT NOT NULL DEFAULT '';
+",
+    "
+    ALTER TABLE completions ADD COLUMN note TEXT;
+",
+    "
+    CREATE TABLE pauses (
+        habit_id TEXT NOT NULL REFERENCES habits(id) ON DELETE CASCADE,
+        start    TEXT NOT NULL,
+        until    TEXT,
+        vacation INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX pauses_habit ON pauses (habit_id);
+",
+];
+
+/// Stores habits in normalized `habits` and `completions` tables.
+#[derive(Debug)]
+pub struct SqliteRepository {
+    path: PathBuf,
+    conn: Connection,
+    lock_timeout: Duration,
+    backup_policy: BackupPolicy,
+}
+
+impl SqliteRepository {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            conn,
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            backup_policy: BackupPolicy::default(),
+        })
+    }
+
+    pub fn with_backup_policy(mut self, policy: BackupPolicy) -> Self {
+        self.backup_policy = policy;
+        self
+    }
+
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    pub fn schema_version(&self) -> Result<usize> {
+        Ok(self
+            .conn
+            .pragma_query_value(None, "user_version", |r| r.get(0))?)
+    }
+}
+
+impl HabitRepository for SqliteRepository {
+    fn load(&self) -> Result<HabitStore> {
+        let settings: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = 'store'", [], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        let settings: Settings = match settings {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Settings::default(),
+        };
+        Ok(HabitStore {
+            settings,
+            habits: load_habits(&self.conn)?,
+            ..HabitStore::default()
+        })
+    }
+
+    /// Writes only the habits that changed since the last load, so a single
+    /// check-in does not rewrite years of history.
+    fn save(&mut self, store: &HabitStore) -> Result<()> {
+        let previous = self.load()?;
+        if !self.backup_policy.is_disabled() && !previous.habits.is_empty() {
+            let backups = Backups::for_store(&self.path);
+            backups.create(&previous)?;
+            backups.prune(&self.backup_policy)?;
+        }
+        let existing: HashMap<Uuid, Habit> =
+            previous.habits.into_iter().map(|h| (h.id, h)).collect();
+        let tx = self.conn.transaction()?;
+        for id in existing.keys() {
+            if !store.habits.iter().any(|h| h.id == *id) {
+                tx.execute("DELETE FROM habits WHERE id = ?1", [id.to_string()])?;
+            }
+        }
+        for (position, habit) in store.habits.iter().enumerate() {
+            match existing.get(&habit.id) {
+                Some(old) if old == habit => {
+                    tx.execute(
+                        "UPDATE habits SET position = ?2 WHERE id = ?1",
+                        params![habit.id.to_string(), position],
+                    )?;
+                }
+                _ => write_habit(&tx, habit, position)?,
+            }
+        }
+        tx.execute(
+            "INSERT INTO settings (key, value) VALUES ('store', ?1)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            [serde_json::to_string(&store.settings)?],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn insert(&mut self, lock: Option<&StoreLock>, habit: Habit) -> Result<()> {
+        let _lock = write_lock(self, lock)?;
+        let tx = self.conn.transaction()?;
+        let exists: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM habits WHERE id = ?1)",
+            [habit.id.to_string()],
+            |r| r.get(0),
+        )?;
+        if exists {
+            return Err(HabitError::AlreadyExists(habit.id.to_string()));
+        }
+        let position: usize = tx.query_row("SELECT COUNT(*) FROM habits", [], |r| r.get(0))?;
+        write_habit(&tx, &habit, position)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn update(&mut self, lock: Option<&StoreLock>, habit: &Habit) -> Result<()> {
+        let _lock = write_lock(self, lock)?;
+        let tx = self.conn.transaction()?;
+        let position: Option<usize> = tx
+            .query_row(
+                "SELECT position FROM habits WHERE id = ?1",
+                [habit.id.to_string()],
+                |r| r.get(0),
+            )
+            .optional()?;
+        let position = position.ok_or_else(|| HabitError::NotFound(habit.id.to_string()))?;
+        write_habit(&tx, habit, position)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn delete(&mut self, lock: Option<&StoreLock>, id: Uuid) -> Result<Habit> {
+        let _lock = write_lock(self, lock)?;
+        let tx = self.conn.transaction()?;
+        let (position, habit) = load_habits(&tx)?
+            .into_iter()
+            .enumerate()
+            .find(|(_, h)| h.id == id)
+            .ok_or_else(|| HabitError::NotFound(id.to_string()))?;
+        let id = id.to_string();
+        tx.execute("DELETE FROM completions WHERE habit_id = ?1", [&id])?;
+        tx.execute("DELETE FROM pauses WHERE habit_id = ?1", [&id])?;
+        tx.execute("DELETE FROM habits WHERE id = ?1", [&id])?;
+        // keep positions dense, since insert appends at the habit count
+        tx.execute(
+            "UPDATE habits SET position = position - 1 WHERE position > ?1",
+            [position],
+        )?;
+        tx.commit()?;
+        Ok(habit)
+    }
+
+    fn location(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn backend(&self) -> Option<Backend> {
+        Some(Backend::Sqlite)
+    }
+
+    fn lock(&self) -> Result<Option<StoreLock>> {
+        StoreLock::acquire(&self.path, self.lock_timeout).map(Some)
+    }
+}
+
+/// Every habit in position order, with its completions and pauses.
+fn load_habits(conn: &Connection) -> Result<Vec<Habit>> {
+    let mut completions: HashMap<String, Vec<Completion>> = HashMap::new();
+    let mut stmt =
+        conn.prepare("SELECT habit_id, at, amount, note FROM completions ORDER BY rowid")?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, Option<f64>>(2)?,
+            r.get::<_, Option<String>>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (habit_id, at, amount, note) = row?;
+        completions.entry(habit_id).or_default().push(Completion {
+            at: parse_time(&at)?,
+            amount,
+            note,
+        });
+    }
+    // `at` is stored only as precise as needed, so its text does not sort
+    // by time: `12:00:00.5Z` comes before `12:00:00Z`
+    for list in completions.values_mut() {
+        list.sort_by_key(|c| c.at);
+    }
+    let mut pauses: HashMap<String, Vec<Pause>> = HashMap::new();
+    let mut stmt = conn
+        .prepare("SELECT habit_id, start, until, vacation FROM pauses ORDER BY habit_id, start")?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, Option<String>>(2)?,
+            r.get::<_, bool>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (habit_id, from, until, vacation) = row?;
+        pauses.entry(habit_id).or_default().push(Pause {
+            from: parse_day(&from)?,
+            until: until.as_deref().map(parse_day).transpose()?,
+            vacation,
+        });
+    }
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, created_at, schedule, goal_target, goal_unit, is_active, tags
+         FROM habits ORDER BY position",
+    )?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, Option<String>>(2)?,
+            r.get::<_, String>(3)?,
+            r.get::<_, String>(4)?,
+            r.get::<_, Option<f64>>(5)?,
+            r.get::<_, Option<String>>(6)?,
+            r.get::<_, bool>(7)?,
+            r.get::<_, String>(8)?,
+        ))
+    })?;
+    let mut habits = Vec::new();
+    for row in rows {
+        let (id, name, description, created_at, schedule, target, unit, is_active, tags) = row?;
+        habits.push(Habit {
+            id: parse_id(&id)?,
+            name,
+            description,
+            created_at: parse_time(&created_at)?,
+            completions: completions.remove(&id).unwrap_or_default(),
+            schedule: schedule.parse()?,
+            goal: target.map(|target| Goal {
+                target,
+                unit: unit.unwrap_or_default(),
+            }),
+            // tags cannot contain commas, so a joined column round-trips
+            tags: tags
+                .split(',')
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect(),
+            is_active,
+            pauses: pauses.remove(&id).unwrap_or_default(),
+        });
+    }
+    Ok(habits)
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    if version > MIGRATIONS.len() {
+        return Err(HabitError::BackendUnavailable(format!(
+            "sqlite schema version {} is newer than this build supports ({})",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+    let tx = conn.transaction()?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn write_habit(tx: &Transaction<'_>, habit: &Habit, position: usize) -> Result<()> {
+    let id = habit.id.to_string();
+    tx.execute(
+        "INSERT INTO habits
+            (id, position, name, description, created_at, schedule, goal_target, goal_unit, is_active, tags)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT (id) DO UPDATE SET
+            position = excluded.position,
+            name = excluded.name,
+            description = excluded.description,
+            created_at = excluded.created_at,
+            schedule = excluded.schedule,
+            goal_target = excluded.goal_target,
+            goal_unit = excluded.goal_unit,
+            is_active = excluded.is_active,
+            tags = excluded.tags",
+        params![
+            id,
+            position,
+            habit.name,
+            habit.description,
+            format_time(habit.created_at),
+            habit.schedule.to_string(),
+            habit.goal.as_ref().map(|g| g.target),
+            habit.goal.as_ref().map(|g| g.unit.as_str()),
+            habit.is_active,
+            habit.tags.join(","),
+        ],
+    )?;
+    tx.execute("DELETE FROM completions WHERE habit_id = ?1", [&id])?;
+    let mut stmt = tx.prepare_cached(
+        "INSERT INTO completions (habit_id, at, amount, note) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for c in &habit.completions {
+        stmt.execute(params![id, format_time(c.at), c.amount, c.note])?;
+    }
+    tx.execute("DELETE FROM pauses WHERE habit_id = ?1", [&id])?;
+    let mut stmt = tx.prepare_cached(
+        "INSERT INTO pauses (habit_id, start, until, vacation) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for p in &habit.pauses {
+        stmt.execute(params![
+            id,
+            p.from.to_string(),
+            p.until.map(|u| u.to_string()),
+            p.vacation
+        ])?;
+    }
+    Ok(())
+}
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|d| d.to_utc())
+        .map_err(|_| HabitError::InvalidDate(s.to_string()))
+}
+
+fn parse_day(s: &str) -> Result<NaiveDate> {
+    s.parse()
+        .map_err(|_| HabitError::InvalidDate(s.to_string()))
+}
+
+fn parse_id(s: &str) -> Result<Uuid> {
+    s.parse()
+        .map_err(|_| HabitError::NotFound(format!("malformed habit id {}", s)))
+}
@@ -0,0 +1,272 @@
+use crate::models::habit::{Habit, Pause};
+use crate::models::timezone::Zone;
+use crate::storage::json_storage::HabitStore;
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    DuplicateId,
+    DuplicateName,
+    EmptyName,
+    UnsortedCompletions,
+    DuplicateCompletions,
+    InvalidAmounts,
+    FutureCompletions,
+    CompletionsBeforeCreated,
+    InvalidPauses,
+}
+
+impl IssueKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            IssueKind::DuplicateId
+            | IssueKind::DuplicateName
+            | IssueKind::EmptyName
+            | IssueKind::InvalidAmounts => Severity::Error,
+            IssueKind::UnsortedCompletions
+            | IssueKind::DuplicateCompletions
+            | IssueKind::FutureCompletions
+            | IssueKind::CompletionsBeforeCreated
+            | IssueKind::InvalidPauses => Severity::Warning,
+        }
+    }
+
+    /// What `--fix` does about it. `None` for future completions, which
+    /// `complete --force` logs on purpose, so they are only reported.
+    pub fn repair(self) -> Option<&'static str> {
+        Some(match self {
+            IssueKind::DuplicateId => "gives the later habit a new id",
+            IssueKind::DuplicateName => "appends a number to the later habit's name",
+            IssueKind::EmptyName => "names it after its id",
+            IssueKind::UnsortedCompletions => "sorts the completions",
+            IssueKind::DuplicateCompletions => "keeps the first completion of each day",
+            IssueKind::InvalidAmounts => "drops those completions",
+            IssueKind::FutureCompletions => return None,
+            IssueKind::CompletionsBeforeCreated => "moves the creation date back",
+            IssueKind::InvalidPauses => "drops those pauses",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub habit: Uuid,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl HabitStore {
+    /// Every integrity problem in the store, such as duplicate ids or names,
+    /// empty names, and unsorted, duplicate or impossible completions.
+    pub fn validate(&self, zone: &Zone) -> Vec<Issue> {
+        self.clone().check(zone, false)
+    }
+
+    /// Repairs what [`HabitStore::validate`] reports, except the issues
+    /// [`IssueKind::repair`] leaves alone, and returns the issues that were
+    /// fixed. No habit is dropped.
+    pub fn repair(&mut self, zone: &Zone) -> Vec<Issue> {
+        let mut fixed = self.check(zone, true);
+        fixed.retain(|i| i.kind.repair().is_some());
+        fixed
+    }
+
+    fn check(&mut self, zone: &Zone, fix: bool) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        let mut report = |kind, habit: Uuid, message: String| {
+            issues.push(Issue {
+                kind,
+                habit,
+                message,
+            })
+        };
+
+        let mut ids = HashSet::new();
+        for habit in &mut self.habits {
+            if !ids.insert(habit.id) {
+                report(
+                    IssueKind::DuplicateId,
+                    habit.id,
+                    format!("'{}' shares id {} with another habit", habit.name, habit.id),
+                );
+                if fix {
+                    habit.id = Uuid::new_v4();
+                    ids.insert(habit.id);
+                }
+            }
+        }
+
+        // a renamed habit must not take a name a later habit already has
+        let taken: HashSet<String> = self
+            .habits
+            .iter()
+            .map(|h| Habit::name_key(&h.name))
+            .collect();
+        let mut names = HashSet::new();
+        for habit in &mut self.habits {
+            if habit.name.trim().is_empty() {
+                report(
+                    IssueKind::EmptyName,
+                    habit.id,
+                    format!("habit {} has an empty name", habit.id),
+                );
+                if fix {
+                    habit.name = format!("Untitled {}", &habit.id.to_string()[..8]);
+                }
+            }
+            if !names.insert(Habit::name_key(&habit.name)) {
+                report(
+                    IssueKind::DuplicateName,
+                    habit.id,
+                    format!(
+                        "'{}' matches another habit's name ignoring ASCII case, so lookups by name are ambiguous",
+                        habit.name
+                    ),
+                );
+                if fix {
+                    let base = habit.name.clone();
+                    let mut n = 2;
+                    let used = |n| {
+                        let candidate = Habit::name_key(&format!("{} ({})", base, n));
+                        names.contains(&candidate) || taken.contains(&candidate)
+                    };
+                    while used(n) {
+                        n += 1;
+                    }
+                    habit.name = format!("{} ({})", base, n);
+                    names.insert(Habit::name_key(&habit.name));
+                }
+            }
+        }
+
+        let now = Utc::now();
+        for habit in &mut self.habits {
+            let name = habit.name.clone();
+            if !habit.completions.is_sorted_by_key(|c| c.at) {
+                report(
+                    IssueKind::UnsortedCompletions,
+                    habit.id,
+                    format!("'{}' has completions out of chronological order", name),
+                );
+                if fix {
+                    habit.completions.sort_by_key(|c| c.at);
+                }
+            }
+
+            let invalid = |amount: Option<f64>| amount.is_some_and(|a| !(a > 0.0 && a.is_finite()));
+            let bad = habit
+                .completions
+                .iter()
+                .filter(|c| invalid(c.amount))
+                .count();
+            if bad > 0 {
+                report(
+                    IssueKind::InvalidAmounts,
+                    habit.id,
+                    format!(
+                        "'{}' has {} completions with a zero, negative or non-numeric amount",
+                        name, bad
+                    ),
+                );
+                if fix {
+                    habit.completions.retain(|c| !invalid(c.amount));
+                }
+            }
+
+            let future = habit.completions.iter().filter(|c| c.at > now).count();
+            if future > 0 {
+                report(
+                    IssueKind::FutureCompletions,
+                    habit.id,
+                    format!("'{}' has {} completions in the future", name, future),
+                );
+            }
+
+            // measurable habits log several amounts a day; plain ones complete once
+            let mut days = HashSet::new();
+            let mut exact = HashSet::new();
+            let keep: Vec<bool> = habit
+                .completions
+                .iter()
+                .map(|c| match habit.goal {
+                    Some(_) => exact.insert((c.at, c.amount.map(f64::to_bits))),
+                    None => days.insert(zone.date_of(c.at)),
+                })
+                .collect();
+            let duplicates = keep.iter().filter(|k| !**k).count();
+            if duplicates > 0 {
+                report(
+                    IssueKind::DuplicateCompletions,
+                    habit.id,
+                    format!("'{}' has {} duplicate completions", name, duplicates),
+                );
+                if fix {
+                    let mut keep = keep.into_iter();
+                    habit.completions.retain(|_| keep.next().unwrap_or(true));
+                }
+            }
+
+            let backwards = |p: &Pause| p.until.is_some_and(|until| until < p.from);
+            let bad = habit.pauses.iter().filter(|p| backwards(p)).count();
+            if bad > 0 {
+                report(
+                    IssueKind::InvalidPauses,
+                    habit.id,
+                    format!("'{}' has {} pauses that end before they start", name, bad),
+                );
+                if fix {
+                    habit.pauses.retain(|p| !backwards(p));
+                }
+            }
+
+            let created = habit.created_day(zone);
+            let early = habit
+                .completions
+                .iter()
+                .filter(|c| zone.date_of(c.at) < created);
+            if let Some(first) = early.clone().map(|c| c.at).min() {
+                report(
+                    IssueKind::CompletionsBeforeCreated,
+                    habit.id,
+                    format!(
+                        "'{}' has {} completions before it was created on {}",
+                        name,
+                        early.count(),
+                        created
+                    ),
+                );
+                if fix {
+                    habit.created_at = first;
+                }
+            }
+        }
+        issues
+    }
+}
@@ -0,0 +1,358 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::goal_from;
+use crate::models::habit::{Completion, Habit};
+use crate::models::timezone::Zone;
+use crate::storage::json_storage::HabitStore;
+use crate::transfer::import::{ImportBatch, ImportRecord, When};
+use chrono::{DateTime, NaiveDate, Utc};
+use csv::{ReaderBuilder, StringRecord, Trim, WriterBuilder};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Columns `habit import` understands, in the order `habit export` writes them.
+pub const FIELDS: [&str; 13] = [
+    "habit_id",
+    "habit",
+    "description",
+    "schedule",
+    "target",
+    "unit",
+    "tags",
+    "active",
+    "created",
+    "date",
+    "at",
+    "amount",
+    "note",
+];
+/// Columns of the completions file in a split export.
+pub const COMPLETION_FIELDS: [&str; 5] = ["habit_id", "date", "at", "amount", "note"];
+const FIELD_NAMES: &str = "habit_id, habit, description, schedule, target, unit, tags, active, created, date, at, amount or note";
+
+/// `--map Source=field`: reads the source column `Source` as `field`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMap {
+    pub source: String,
+    pub field: String,
+}
+
+impl FromStr for ColumnMap {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (source, field) = s.split_once('=').ok_or_else(|| {
+            HabitError::InvalidImport(format!("bad mapping {} (use Source=field)", s))
+        })?;
+        let field = field.trim().to_ascii_lowercase();
+        if !FIELDS.contains(&field.as_str()) {
+            return Err(HabitError::UnknownColumn(field, FIELD_NAMES));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            field,
+        })
+    }
+}
+
+/// Tab for `.tsv` files, comma for anything else.
+pub fn delimiter(path: &Path) -> u8 {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("tsv") => b'\t',
+        _ => b',',
+    }
+}
+
+/// Reads rows with a header line. Columns named like [`FIELDS`], or mapped
+/// onto one, are used and the rest reported; `name` labels errors.
+pub fn read(
+    reader: impl io::Read,
+    name: &str,
+    delimiter: u8,
+    mappings: &[ColumnMap],
+) -> Result<ImportBatch> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+    let mut batch = ImportBatch::default();
+    let mut columns: HashMap<String, usize> = HashMap::new();
+    for (i, header) in reader.headers()?.iter().enumerate() {
+        let field = mappings
+            .iter()
+            .find(|m| m.source.eq_ignore_ascii_case(header))
+            .map_or_else(|| header.to_ascii_lowercase(), |m| m.field.clone());
+        if FIELDS.contains(&field.as_str()) {
+            columns.entry(field).or_insert(i);
+        } else if !header.is_empty() {
+            batch
+                .unmapped
+                .push(format!("{}: column '{}' ignored", name, header));
+        }
+    }
+    if !columns.contains_key("habit") && !columns.contains_key("habit_id") {
+        return Err(HabitError::InvalidImport(format!(
+            "{}: no habit or habit_id column (map one with --map Source=habit)",
+            name
+        )));
+    }
+    for row in reader.records() {
+        let row = row?;
+        let line = row.position().map_or(0, |p| p.line());
+        batch
+            .records
+            .push(record(&row, &columns, &format!("{}:{}", name, line))?);
+    }
+    Ok(batch)
+}
+
+/// Reads two columns, `date,habit`, one check-in per line. A first line
+/// that does not start with a date is taken for a header.
+pub fn read_date_habit(reader: impl io::Read, name: &str, delimiter: u8) -> Result<ImportBatch> {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .trim(Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+    let mut batch = ImportBatch::default();
+    for (i, row) in reader.records().enumerate() {
+        let row = row?;
+        let source = format!("{}:{}", name, row.position().map_or(0, |p| p.line()));
+        let (date, habit) = (
+            row.get(0).unwrap_or_default(),
+            row.get(1).unwrap_or_default(),
+        );
+        let Some(check_in) = When::parse(date) else {
+            if i == 0 {
+                continue;
+            }
+            return Err(HabitError::InvalidImport(format!(
+                "{}: invalid date {:?}",
+                source, date
+            )));
+        };
+        if habit.is_empty() {
+            return Err(HabitError::InvalidImport(format!(
+                "{}: no habit after the date",
+                source
+            )));
+        }
+        if row.len() > 2 && batch.unmapped.is_empty() {
+            batch
+                .unmapped
+                .push(format!("{}: columns after date,habit ignored", name));
+        }
+        batch.records.push(ImportRecord {
+            source,
+            name: Some(habit.to_string()),
+            check_in: Some(check_in),
+            ..ImportRecord::default()
+        });
+    }
+    Ok(batch)
+}
+
+fn record(
+    row: &StringRecord,
+    columns: &HashMap<String, usize>,
+    source: &str,
+) -> Result<ImportRecord> {
+    let get = |field: &str| {
+        columns
+            .get(field)
+            .and_then(|i| row.get(*i))
+            .filter(|v| !v.is_empty())
+    };
+    let parse = |field: &str, value: &str| {
+        HabitError::InvalidImport(format!("{}: invalid {} {:?}", source, field, value))
+    };
+    let when = |field: &str| {
+        get(field)
+            .map(|v| When::parse(v).ok_or_else(|| parse(field, v)))
+            .transpose()
+    };
+    let number = |field: &str| {
+        get(field)
+            .map(|v| v.parse::<f64>().map_err(|_| parse(field, v)))
+            .transpose()
+    };
+    Ok(ImportRecord {
+        source: source.to_string(),
+        habit_id: get("habit_id")
+            .map(|v| v.parse().map_err(|_| parse("habit_id", v)))
+            .transpose()?,
+        name: get("habit").map(str::to_string),
+        description: get("description").map(str::to_string),
+        schedule: get("schedule")
+            .map(|v| v.parse().map_err(|_| parse("schedule", v)))
+            .transpose()?,
+        goal: number("target")?
+            .map(|t| goal_from(t, get("unit").unwrap_or_default().to_string()))
+            .transpose()
+            .map_err(|e| HabitError::InvalidImport(format!("{}: {}", source, e)))?,
+        tags: get("tags")
+            .map(|v| {
+                v.split([';', ','])
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        active: get("active")
+            .map(|v| match v.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Ok(true),
+                "false" | "no" | "0" => Ok(false),
+                _ => Err(parse("active", v)),
+            })
+            .transpose()?,
+        created: when("created")?,
+        // an exact time wins over the day it falls on
+        check_in: when("at")?.or(when("date")?),
+        amount: number("amount")?,
+        note: get("note").map(str::to_string),
+    })
+}
+
+/// A habit's columns in `habit export`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HabitRow {
+    pub habit_id: Uuid,
+    pub habit: String,
+    pub description: Option<String>,
+    pub schedule: String,
+    pub target: Option<f64>,
+    pub unit: Option<String>,
+    /// Joined with `;`.
+    pub tags: String,
+    pub active: bool,
+    pub created: DateTime<Utc>,
+}
+
+impl From<&Habit> for HabitRow {
+    fn from(h: &Habit) -> Self {
+        Self {
+            habit_id: h.id,
+            habit: h.name.clone(),
+            description: h.description.clone(),
+            schedule: h.schedule.to_string(),
+            target: h.goal.as_ref().map(|g| g.target),
+            unit: h.goal.as_ref().map(|g| g.unit.clone()),
+            tags: h.tags.join(";"),
+            active: h.is_active,
+            created: h.created_at,
+        }
+    }
+}
+
+/// One check-in in the completions file of a split export.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionRow {
+    pub habit_id: Uuid,
+    /// Local day, for people and spreadsheets; `at` is what import reads.
+    pub date: NaiveDate,
+    pub at: DateTime<Utc>,
+    pub amount: Option<f64>,
+    pub note: Option<String>,
+}
+
+impl CompletionRow {
+    pub fn new(habit: &Habit, completion: &Completion, zone: &Zone) -> Self {
+        Self {
+            habit_id: habit.id,
+            date: zone.date_of(completion.at),
+            at: completion.at,
+            amount: completion.amount,
+            note: completion.note.clone(),
+        }
+    }
+}
+
+/// A row of the single-file export: a habit with one of its check-ins.
+/// Habits never completed get one row with the check-in columns empty.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub habit_id: Uuid,
+    pub habit: String,
+    pub description: Option<String>,
+    pub schedule: String,
+    pub target: Option<f64>,
+    pub unit: Option<String>,
+    pub tags: String,
+    pub active: bool,
+    pub created: DateTime<Utc>,
+    pub date: Option<NaiveDate>,
+    pub at: Option<DateTime<Utc>>,
+    pub amount: Option<f64>,
+    pub note: Option<String>,
+}
+
+impl ExportRow {
+    fn new(habit: HabitRow, completion: Option<CompletionRow>) -> Self {
+        Self {
+            habit_id: habit.habit_id,
+            habit: habit.habit,
+            description: habit.description,
+            schedule: habit.schedule,
+            target: habit.target,
+            unit: habit.unit,
+            tags: habit.tags,
+            active: habit.active,
+            created: habit.created,
+            date: completion.as_ref().map(|c| c.date),
+            at: completion.as_ref().map(|c| c.at),
+            amount: completion.as_ref().and_then(|c| c.amount),
+            note: completion.and_then(|c| c.note),
+        }
+    }
+}
+
+pub fn export_rows(store: &HabitStore, zone: &Zone) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for habit in &store.habits {
+        if habit.completions.is_empty() {
+            rows.push(ExportRow::new(HabitRow::from(habit), None));
+        }
+        for c in &habit.completions {
+            let completion = CompletionRow::new(habit, c, zone);
+            rows.push(ExportRow::new(HabitRow::from(habit), Some(completion)));
+        }
+    }
+    rows
+}
+
+pub fn habit_rows(store: &HabitStore) -> Vec<HabitRow> {
+    store.habits.iter().map(HabitRow::from).collect()
+}
+
+pub fn completion_rows(store: &HabitStore, zone: &Zone) -> Vec<CompletionRow> {
+    store
+        .habits
+        .iter()
+        .flat_map(|h| h.completions.iter().map(|c| CompletionRow::new(h, c, zone)))
+        .collect()
+}
+
+/// Writes `rows` with a header line, even when there are none.
+pub fn write<T: Serialize>(
+    writer: impl io::Write,
+    delimiter: u8,
+    header: &[&str],
+    rows: &[T],
+) -> Result<()> {
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(writer);
+    writer.write_record(header)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use crate::error::{HabitError, Result};
+use crate::models::schedule::Schedule;
+use crate::transfer::import::{self, ImportBatch, ImportRecord, When};
+use chrono::{DateTime, Utc, Weekday};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+
+/// Habitica's keys for `repeat`, in week order.
+const REPEAT: [(&str, Weekday); 7] = [
+    ("m", Weekday::Mon),
+    ("t", Weekday::Tue),
+    ("w", Weekday::Wed),
+    ("th", Weekday::Thu),
+    ("f", Weekday::Fri),
+    ("s", Weekday::Sat),
+    ("su", Weekday::Sun),
+];
+
+/// The user data export, or a bare task list as the API returns it.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Document {
+    User {
+        tasks: Tasks,
+        #[serde(default)]
+        tags: Vec<Tag>,
+    },
+    Tasks(Vec<Task>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Tasks {
+    Grouped {
+        #[serde(default)]
+        habits: Vec<Task>,
+        #[serde(default)]
+        dailys: Vec<Task>,
+        #[serde(default)]
+        todos: Vec<Task>,
+        #[serde(default)]
+        rewards: Vec<Task>,
+    },
+    List(Vec<Task>),
+}
+
+#[derive(Deserialize)]
+struct Tag {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Task {
+    #[serde(alias = "_id")]
+    id: Option<String>,
+    #[serde(rename = "type")]
+    kind: String,
+    text: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    created_at: Option<DateTime<Utc>>,
+    frequency: Option<String>,
+    every_x: Option<u32>,
+    repeat: Option<HashMap<String, bool>>,
+    #[serde(default)]
+    days_of_month: Vec<u32>,
+    #[serde(default)]
+    weeks_of_month: Vec<u32>,
+    #[serde(default = "yes")]
+    up: bool,
+    #[serde(default)]
+    history: Vec<History>,
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// A day's entry: dailies record whether they were completed, habits how
+/// often they were scored up.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct History {
+    date: Stamp,
+    completed: Option<bool>,
+    scored_up: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stamp {
+    Millis(i64),
+    Text(DateTime<Utc>),
+}
+
+/// Reads habits and dailies with their history. To-dos and rewards have no
+/// equivalent and are reported, as are schedules that only fit roughly.
+pub fn read(reader: impl io::Read, name: &str) -> Result<ImportBatch> {
+    let document = serde_json::from_reader(reader).map_err(|e| {
+        HabitError::InvalidImport(format!("{}: not a Habitica export ({})", name, e))
+    })?;
+    let (tasks, tags) = match document {
+        Document::User { tasks, tags } => (tasks, tags),
+        Document::Tasks(tasks) => (Tasks::List(tasks), Vec::new()),
+    };
+    let tasks = match tasks {
+        Tasks::Grouped {
+            habits,
+            dailys,
+            todos,
+            rewards,
+        } => [habits, dailys, todos, rewards]
+            .into_iter()
+            .flatten()
+            .collect(),
+        Tasks::List(tasks) => tasks,
+    };
+    let tags: HashMap<String, String> = tags.into_iter().map(|t| (t.id, t.name)).collect();
+
+    let mut batch = ImportBatch::default();
+    let (mut todos, mut rewards) = (0, 0);
+    for (i, task) in tasks.iter().enumerate() {
+        let source = format!("{}: task {} ('{}')", name, i + 1, task.text);
+        let schedule = match task.kind.as_str() {
+            "habit" if !task.up => {
+                batch.unmapped.push(format!(
+                    "'{}': a habit that only scores down; skipped",
+                    task.text
+                ));
+                continue;
+            }
+            "habit" => match task.frequency.as_deref() {
+                Some("weekly") => Schedule::TimesPerWeek(1),
+                Some("monthly") => Schedule::TimesPerMonth(1),
+                _ => Schedule::Daily,
+            },
+            "daily" => daily_schedule(task, &mut batch),
+            "todo" => {
+                todos += 1;
+                continue;
+            }
+            "reward" => {
+                rewards += 1;
+                continue;
+            }
+            other => {
+                batch.unmapped.push(format!(
+                    "'{}': unknown task type {}; skipped",
+                    task.text, other
+                ));
+                continue;
+            }
+        };
+        batch.records.push(ImportRecord {
+            source: source.clone(),
+            habit_id: task.id.as_deref().and_then(|id| id.parse().ok()),
+            name: Some(task.text.clone()),
+            description: Some(task.notes.clone()).filter(|n| !n.is_empty()),
+            schedule: Some(schedule),
+            tags: task
+                .tags
+                .iter()
+                .filter_map(|id| tags.get(id))
+                .filter_map(|t| import::tag(t))
+                .collect(),
+            created: task.created_at.map(When::At),
+            ..ImportRecord::default()
+        });
+        for entry in &task.history {
+            let done = match task.kind.as_str() {
+                "habit" => entry.scored_up.unwrap_or(0) > 0,
+                _ => entry.completed.unwrap_or(false),
+            };
+            let at = match entry.date {
+                Stamp::Millis(ms) => DateTime::from_timestamp_millis(ms),
+                Stamp::Text(at) => Some(at),
+            };
+            if let Some(at) = at.filter(|_| done) {
+                batch.records.push(ImportRecord {
+                    source: source.clone(),
+                    name: Some(task.text.clone()),
+                    check_in: Some(When::At(at)),
+                    ..ImportRecord::default()
+                });
+            }
+        }
+    }
+    if todos + rewards > 0 {
+        batch.unmapped.push(format!(
+            "{} to-do(s) and {} reward(s) skipped",
+            todos, rewards
+        ));
+    }
+    Ok(batch)
+}
+
+/// Maps a daily's repeat rule onto a schedule, noting what does not fit.
+fn daily_schedule(task: &Task, batch: &mut ImportBatch) -> Schedule {
+    let every = task.every_x.unwrap_or(1).max(1);
+    let mut approximate = |what: &str, schedule: Schedule| {
+        batch
+            .unmapped
+            .push(format!("'{}': {} became {}", task.text, what, schedule));
+        schedule
+    };
+    match task.frequency.as_deref().unwrap_or("weekly") {
+        "daily" if every == 1 => Schedule::Daily,
+        "daily" => Schedule::EveryNDays(every),
+        "weekly" => {
+            let days: Vec<Weekday> = REPEAT
+                .iter()
+                .filter(|(key, _)| {
+                    task.repeat
+                        .as_ref()
+                        .is_none_or(|r| r.get(*key).copied().unwrap_or(false))
+                })
+                .map(|(_, day)| *day)
+                .collect();
+            let schedule = match days.len() {
+                0 => return approximate("no repeat days", Schedule::Daily),
+                7 => Schedule::Daily,
+                _ => Schedule::Weekdays(days),
+            };
+            if every > 1 {
+                approximate(&format!("every {} weeks", every), schedule)
+            } else {
+                schedule
+            }
+        }
+        "monthly" if !task.days_of_month.is_empty() => {
+            let mut days = task.days_of_month.clone();
+            days.sort_unstable();
+            days.dedup();
+            let schedule = Schedule::DaysOfMonth(days);
+            if every > 1 {
+                approximate(&format!("every {} months", every), schedule)
+            } else {
+                schedule
+            }
+        }
+        "monthly" => {
+            let weekdays = task
+                .repeat
+                .as_ref()
+                .map_or(1, |r| r.values().filter(|on| **on).count().max(1));
+            let times = (task.weeks_of_month.len().max(1) * weekdays) as u32;
+            approximate("certain weeks of the month", Schedule::TimesPerMonth(times))
+        }
+        "yearly" => approximate("a yearly repeat", Schedule::TimesPerMonth(1)),
+        other => approximate(&format!("frequency '{}'", other), Schedule::Daily),
+    }
+}
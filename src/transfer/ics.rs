@@ -0,0 +1,217 @@
+use crate::error::Result;
+use crate::models::habit::{Completion, Habit};
+use crate::models::schedule::{PeriodKind, Schedule};
+use crate::models::timezone::Zone;
+use crate::storage::json_storage::HabitStore;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::io;
+
+const PRODID: &str = "-//habit//Habit Tracker CLI//EN";
+/// Longest content line in octets before it is folded (RFC 5545 3.1).
+const LINE_LIMIT: usize = 75;
+
+/// Writes `store` as an iCalendar feed: a recurring all-day event for each
+/// active habit's schedule and a completed to-do for each check-in. UIDs
+/// come from habit ids and check-in times, so a calendar reading the file
+/// again updates its entries instead of adding copies. `now` stamps them.
+pub fn write(
+    mut writer: impl io::Write,
+    store: &HabitStore,
+    zone: &Zone,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    let mut feed = Feed::default();
+    feed.line("BEGIN:VCALENDAR");
+    feed.line("VERSION:2.0");
+    feed.prop("PRODID", PRODID);
+    feed.line("CALSCALE:GREGORIAN");
+    feed.line("METHOD:PUBLISH");
+    feed.line("X-WR-CALNAME:Habits");
+    // hints for subscribed calendars on how often to re-read the file
+    feed.line("REFRESH-INTERVAL;VALUE=DURATION:PT1H");
+    feed.line("X-PUBLISHED-TTL:PT1H");
+    let stamp = timestamp(now);
+    for habit in store.habits.iter().filter(|h| h.is_active) {
+        event(&mut feed, habit, zone, &stamp);
+    }
+    for habit in &store.habits {
+        // entries logged within the same second, e.g. imported amounts that
+        // landed on one midday, are told apart by their order
+        let mut repeat = 0;
+        for (i, completion) in habit.completions.iter().enumerate() {
+            let same = i > 0 && timestamp(habit.completions[i - 1].at) == timestamp(completion.at);
+            repeat = if same { repeat + 1 } else { 0 };
+            todo(&mut feed, habit, completion, repeat, zone, &stamp);
+        }
+    }
+    feed.line("END:VCALENDAR");
+    writer.write_all(feed.text.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The UID of a habit's recurring event.
+fn habit_uid(habit: &Habit) -> String {
+    format!("{}@habit", habit.id)
+}
+
+/// The UID of one check-in's to-do; `repeat` counts earlier entries logged
+/// in the same second.
+fn completion_uid(habit: &Habit, completion: &Completion, repeat: usize) -> String {
+    match repeat {
+        0 => format!("{}-{}@habit", habit.id, timestamp(completion.at)),
+        n => format!("{}-{}-{}@habit", habit.id, timestamp(completion.at), n + 1),
+    }
+}
+
+fn event(feed: &mut Feed, habit: &Habit, zone: &Zone, stamp: &str) {
+    let summary = match habit.schedule.period() {
+        PeriodKind::Day => habit.name.clone(),
+        _ => format!("{} ({})", habit.name, habit.schedule),
+    };
+    let mut details = vec![format!("Schedule: {}", habit.schedule)];
+    if let Some(goal) = &habit.goal {
+        details.push(
+            format!("Target: {} {}", goal.target, goal.unit)
+                .trim_end()
+                .into(),
+        );
+    }
+    details.extend(habit.description.clone());
+
+    feed.line("BEGIN:VEVENT");
+    feed.prop("UID", &habit_uid(habit));
+    feed.prop("DTSTAMP", stamp);
+    feed.prop("DTSTART;VALUE=DATE", &date(first_day(habit, zone)));
+    feed.line("DURATION:P1D");
+    feed.prop("RRULE", &rule(&habit.schedule));
+    feed.prop("SUMMARY", &escape(&summary));
+    feed.prop("DESCRIPTION", &escape(&details.join("\n")));
+    if !habit.tags.is_empty() {
+        let tags: Vec<String> = habit.tags.iter().map(|t| escape(t)).collect();
+        feed.prop("CATEGORIES", &tags.join(","));
+    }
+    feed.line("TRANSP:TRANSPARENT");
+    feed.line("END:VEVENT");
+}
+
+fn todo(
+    feed: &mut Feed,
+    habit: &Habit,
+    completion: &Completion,
+    repeat: usize,
+    zone: &Zone,
+    stamp: &str,
+) {
+    let mut details = Vec::new();
+    if let Some(amount) = completion.amount {
+        let unit = habit.goal.as_ref().map_or("", |g| g.unit.as_str());
+        details.push(format!("{} {}", amount, unit).trim_end().to_string());
+    }
+    details.extend(completion.note.clone());
+
+    feed.line("BEGIN:VTODO");
+    feed.prop("UID", &completion_uid(habit, completion, repeat));
+    feed.prop("DTSTAMP", stamp);
+    feed.prop("DTSTART;VALUE=DATE", &date(zone.date_of(completion.at)));
+    feed.prop("SUMMARY", &escape(&habit.name));
+    if !details.is_empty() {
+        feed.prop("DESCRIPTION", &escape(&details.join("\n")));
+    }
+    feed.prop("COMPLETED", &timestamp(completion.at));
+    feed.line("STATUS:COMPLETED");
+    feed.line("PERCENT-COMPLETE:100");
+    feed.prop("RELATED-TO", &habit_uid(habit));
+    feed.line("END:VTODO");
+}
+
+/// The recurrence of a schedule. Quota schedules recur once per week or
+/// month, on the day their period starts.
+fn rule(schedule: &Schedule) -> String {
+    match schedule {
+        Schedule::Daily => "FREQ=DAILY".into(),
+        Schedule::Weekdays(days) => {
+            let days: Vec<String> = days
+                .iter()
+                .map(|d| d.to_string()[..2].to_uppercase())
+                .collect();
+            format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+        }
+        Schedule::TimesPerWeek(_) => "FREQ=WEEKLY".into(),
+        Schedule::EveryNDays(n) => format!("FREQ=DAILY;INTERVAL={}", n),
+        Schedule::TimesPerMonth(_) => "FREQ=MONTHLY".into(),
+        Schedule::DaysOfMonth(days) => {
+            // day 31 means the month's last day, which -1 says exactly
+            let days: Vec<String> = days
+                .iter()
+                .map(|&d| if d >= 31 { "-1".into() } else { d.to_string() })
+                .collect();
+            format!("FREQ=MONTHLY;BYMONTHDAY={}", days.join(","))
+        }
+    }
+}
+
+/// The first occurrence, which calendars expect `DTSTART` to be. Pauses
+/// are left out; the rule only describes the schedule.
+fn first_day(habit: &Habit, zone: &Zone) -> NaiveDate {
+    let created = habit.created_day(zone);
+    match habit.schedule.period() {
+        PeriodKind::Day => created
+            .iter_days()
+            .take(62)
+            .find(|d| habit.schedule.is_scheduled(*d, created))
+            .unwrap_or(created),
+        period => period.start_of(created),
+    }
+}
+
+fn date(day: NaiveDate) -> String {
+    day.format("%Y%m%d").to_string()
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A TEXT value with its special characters escaped.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Content lines with CRLF endings, folded at [`LINE_LIMIT`] octets
+/// without splitting a character.
+#[derive(Default)]
+struct Feed {
+    text: String,
+}
+
+impl Feed {
+    fn prop(&mut self, name: &str, value: &str) {
+        self.line(&format!("{}:{}", name, value));
+    }
+
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > LINE_LIMIT {
+                self.text.push_str("\r\n ");
+                width = 1;
+            }
+            self.text.push(c);
+            width += c.len_utf8();
+        }
+        self.text.push_str("\r\n");
+    }
+}
@@ -0,0 +1,346 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::{Goal, Habit};
+use crate::models::schedule::Schedule;
+use crate::models::timezone::Zone;
+use crate::storage::json_storage::HabitStore;
+use crate::transfer::csv::{self, ColumnMap};
+use crate::transfer::{habitica, loop_tracker};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// The kinds of file `habit import --from` reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Source {
+    /// `habit export` output, or any CSV/TSV with `--map`.
+    #[default]
+    Csv,
+    /// Loop Habit Tracker: the unzipped CSV export or a `.db` backup.
+    Loop,
+    /// Habitica's JSON data export.
+    Habitica,
+    /// Two columns, `date,habit`, one check-in per line.
+    DateHabit,
+}
+
+impl FromStr for Source {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Source::Csv),
+            "loop" => Ok(Source::Loop),
+            "habitica" => Ok(Source::Habitica),
+            "date-habit" => Ok(Source::DateHabit),
+            _ => Err(HabitError::InvalidImport(format!(
+                "unknown source {} (use csv, loop, habitica or date-habit)",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Csv => write!(f, "csv"),
+            Source::Loop => write!(f, "loop"),
+            Source::Habitica => write!(f, "habitica"),
+            Source::DateHabit => write!(f, "date-habit"),
+        }
+    }
+}
+
+/// The rows read from one file, and what it held that had no exact
+/// equivalent here.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportBatch {
+    pub records: Vec<ImportRecord>,
+    /// One line per thing left out or approximated.
+    pub unmapped: Vec<String>,
+}
+
+/// Reads `path` as `source`; `mappings` rename CSV columns.
+pub fn read(source: Source, path: &Path, mappings: &[ColumnMap]) -> Result<ImportBatch> {
+    let name = path.display().to_string();
+    if source != Source::Csv && !mappings.is_empty() {
+        return Err(HabitError::InvalidImport(
+            "--map only applies to --from csv".into(),
+        ));
+    }
+    match source {
+        Source::Csv => csv::read(open(path)?, &name, csv::delimiter(path), mappings),
+        Source::DateHabit => csv::read_date_habit(open(path)?, &name, csv::delimiter(path)),
+        Source::Loop => loop_tracker::read(path),
+        Source::Habitica => habitica::read(open(path)?, &name),
+    }
+}
+
+pub(crate) fn open(path: &Path) -> Result<File> {
+    File::open(path).map_err(|e| HabitError::InvalidImport(format!("{}: {}", path.display(), e)))
+}
+
+/// Another app's tag as a valid tag here: spaces and commas become dashes.
+pub fn tag(name: &str) -> Option<String> {
+    let parts: Vec<&str> = name
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("-"))
+}
+
+/// One row read from another app or file: a habit, and optionally one
+/// check-in of it. Fields the source does not have stay empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportRecord {
+    /// Where the row came from, e.g. `habits.csv:12`, for error messages.
+    pub source: String,
+    pub habit_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub schedule: Option<Schedule>,
+    pub goal: Option<Goal>,
+    pub tags: Vec<String>,
+    pub active: Option<bool>,
+    pub created: Option<When>,
+    pub check_in: Option<When>,
+    pub amount: Option<f64>,
+    pub note: Option<String>,
+}
+
+/// A point in time as the source gives it. Bare days land on local midday,
+/// like backdated completions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Day(NaiveDate),
+    At(DateTime<Utc>),
+}
+
+impl When {
+    /// Parses an RFC 3339 timestamp or a `YYYY-MM-DD` day.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        DateTime::parse_from_rfc3339(s)
+            .map(|at| When::At(at.to_utc()))
+            .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d").map(When::Day))
+            .ok()
+    }
+
+    pub fn instant(self, zone: &Zone) -> DateTime<Utc> {
+        match self {
+            When::Day(day) => zone.midday(day),
+            When::At(at) => at,
+        }
+    }
+}
+
+/// Which key decides that an imported habit is one the store already has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergeBy {
+    #[default]
+    Name,
+    Id,
+}
+
+impl FromStr for MergeBy {
+    type Err = HabitError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "name" => Ok(MergeBy::Name),
+            "id" => Ok(MergeBy::Id),
+            _ => Err(HabitError::InvalidImport(format!(
+                "unknown merge key {} (use name or id)",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for MergeBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeBy::Name => write!(f, "name"),
+            MergeBy::Id => write!(f, "id"),
+        }
+    }
+}
+
+/// What an import changed, or would change on a dry run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    /// Names of habits the import created.
+    pub created: Vec<String>,
+    /// Names of habits already in the store that rows matched.
+    pub matched: Vec<String>,
+    pub added: usize,
+    /// Check-ins skipped because their day was already logged or the row
+    /// repeated an earlier one.
+    pub duplicates: usize,
+}
+
+/// Adds `records` to `store` in order. Each row's habit is matched `by` name
+/// or id and created when missing; existing habits keep their fields. A
+/// check-in is skipped when the store already had one that day or the batch
+/// repeats an earlier row's time, amount and note, and history
+/// older than a habit moves its creation back, as `doctor --fix` would. The
+/// store is left alone when any row is invalid.
+pub fn merge(
+    store: &mut HabitStore,
+    records: &[ImportRecord],
+    by: MergeBy,
+    zone: &Zone,
+) -> Result<ImportSummary> {
+    let mut merged = store.clone();
+    let mut summary = ImportSummary::default();
+    let logged: HashSet<(Uuid, NaiveDate)> = store
+        .habits
+        .iter()
+        .flat_map(|h| h.completions.iter().map(|c| (h.id, zone.date_of(c.at))))
+        .collect();
+    // source ids seen on rows that matched by name, for rows that only carry the id
+    let mut aliases: HashMap<Uuid, usize> = HashMap::new();
+    // check-ins already taken from this batch, so a repeated row can't add its amount twice
+    let mut seen = HashSet::new();
+
+    for record in records {
+        let invalid =
+            |message: String| HabitError::InvalidImport(format!("{}: {}", record.source, message));
+        let index = match find(&merged, &aliases, record, by) {
+            Some(index) => {
+                let name = &merged.habits[index].name;
+                if !summary.created.contains(name) && !summary.matched.contains(name) {
+                    summary.matched.push(name.clone());
+                }
+                index
+            }
+            None => {
+                let habit = new_habit(&merged, record, zone)?;
+                summary.created.push(habit.name.clone());
+                merged.habits.push(habit);
+                merged.habits.len() - 1
+            }
+        };
+        if let Some(id) = record.habit_id {
+            aliases.insert(id, index);
+        }
+
+        let Some(check_in) = record.check_in else {
+            continue;
+        };
+        let habit = &mut merged.habits[index];
+        let at = check_in.instant(zone);
+        let day = zone.date_of(at);
+        if logged.contains(&(habit.id, day)) {
+            summary.duplicates += 1;
+            continue;
+        }
+        if let Some(amount) = record.amount
+            && !(amount > 0.0 && amount.is_finite())
+        {
+            return Err(invalid(format!("invalid amount {}", amount)));
+        }
+        // amounts only mean something against a daily target
+        let amount = record.amount.filter(|_| habit.goal.is_some());
+        if !seen.insert((
+            habit.id,
+            at,
+            amount.map(f64::to_bits),
+            record.note.as_deref(),
+        )) {
+            summary.duplicates += 1;
+            continue;
+        }
+        match amount {
+            Some(amount) => {
+                habit.log_amount(at, amount, zone);
+            }
+            None if !habit.mark_complete(at, zone) => {
+                summary.duplicates += 1;
+                continue;
+            }
+            None => {}
+        }
+        if let Some(note) = &record.note {
+            habit.set_note(at, note);
+        }
+        if day < habit.created_day(zone) {
+            habit.created_at = at;
+        }
+        summary.added += 1;
+    }
+    *store = merged;
+    Ok(summary)
+}
+
+fn find(
+    store: &HabitStore,
+    aliases: &HashMap<Uuid, usize>,
+    record: &ImportRecord,
+    by: MergeBy,
+) -> Option<usize> {
+    let by_id = || {
+        let id = record.habit_id?;
+        aliases
+            .get(&id)
+            .copied()
+            .or_else(|| store.habits.iter().position(|h| h.id == id))
+    };
+    let by_name = || {
+        let name = record.name.as_deref()?.trim();
+        store.habits.iter().position(|h| h.has_name(name))
+    };
+    // a row missing the chosen key falls back to the other one
+    match by {
+        MergeBy::Name if record.name.is_some() => by_name(),
+        MergeBy::Id if record.habit_id.is_some() => by_id(),
+        _ => by_id().or_else(by_name),
+    }
+}
+
+fn new_habit(store: &HabitStore, record: &ImportRecord, zone: &Zone) -> Result<Habit> {
+    let invalid =
+        |message: String| HabitError::InvalidImport(format!("{}: {}", record.source, message));
+    let Some(name) = record
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+    else {
+        return Err(invalid(match record.habit_id {
+            Some(id) => format!("no habit {} and no name to create it from", id),
+            None => "no habit name or id".into(),
+        }));
+    };
+    if store.habits.iter().any(|h| h.has_name(name)) {
+        return Err(invalid(format!(
+            "'{}' already exists under another id (merge by name to combine them)",
+            name
+        )));
+    }
+    let mut habit = Habit::new(
+        name.to_string(),
+        record.description.clone(),
+        record.schedule.clone().unwrap_or_default(),
+    );
+    // keep the source's id unless it is taken, so later imports can match it
+    if let Some(id) = record.habit_id
+        && !store.habits.iter().any(|h| h.id == id)
+    {
+        habit.id = id;
+    }
+    habit.goal = record.goal.clone();
+    habit
+        .add_tags(record.tags.clone())
+        .map_err(|e| invalid(e.to_string()))?;
+    habit.is_active = record.active.unwrap_or(true);
+    if let Some(created) = record.created {
+        habit.created_at = created.instant(zone);
+    }
+    Ok(habit)
+}
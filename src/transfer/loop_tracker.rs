@@ -0,0 +1,332 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::goal_from;
+use crate::models::schedule::Schedule;
+use crate::transfer::import::{ImportBatch, ImportRecord, When, open};
+use chrono::NaiveDate;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A habit as Loop describes it: `num` repetitions every `den` days, with
+/// a daily amount to reach for numerical habits.
+struct LoopHabit {
+    source: String,
+    name: String,
+    description: Option<String>,
+    num: u32,
+    den: u32,
+    numerical: bool,
+    at_most: bool,
+    target: Option<f64>,
+    unit: Option<String>,
+    archived: bool,
+}
+
+impl LoopHabit {
+    fn record(&self, batch: &mut ImportBatch) -> Result<ImportRecord> {
+        let goal = match self.target.filter(|_| self.numerical) {
+            Some(target) => Some(
+                goal_from(target, self.unit.clone().unwrap_or_default())
+                    .map_err(|e| HabitError::InvalidImport(format!("{}: {}", self.source, e)))?,
+            ),
+            None => None,
+        };
+        if goal.is_some() && self.at_most {
+            batch.unmapped.push(format!(
+                "'{}': an at-most target became a daily target to reach",
+                self.name
+            ));
+        }
+        Ok(ImportRecord {
+            source: self.source.clone(),
+            name: Some(self.name.clone()),
+            description: self.description.clone(),
+            schedule: Some(self.schedule(batch)),
+            goal,
+            active: Some(!self.archived),
+            ..ImportRecord::default()
+        })
+    }
+
+    fn schedule(&self, batch: &mut ImportBatch) -> Schedule {
+        let (num, den) = (self.num.max(1), self.den.max(1));
+        match (num, den) {
+            _ if num >= den => Schedule::Daily,
+            (n, 7) => Schedule::TimesPerWeek(n),
+            (n, 30 | 31) => Schedule::TimesPerMonth(n),
+            (1, d) => Schedule::EveryNDays(d),
+            _ => {
+                let weekly = (f64::from(num) * 7.0 / f64::from(den)).round().max(1.0) as u32;
+                let schedule = Schedule::from_weekly_target(weekly);
+                batch.unmapped.push(format!(
+                    "'{}': {} times in {} days became {}",
+                    self.name, num, den, schedule
+                ));
+                schedule
+            }
+        }
+    }
+
+    /// The check-in for one of Loop's entries, if it is one. Yes/no habits
+    /// store 2 for a check (1 in old versions) and 3 for a skipped day;
+    /// numerical habits store the amount.
+    fn check_in(
+        &self,
+        day: NaiveDate,
+        value: f64,
+        source: String,
+        skipped: &mut BTreeMap<String, u32>,
+    ) -> Option<ImportRecord> {
+        let amount = match value as i64 {
+            _ if self.numerical => Some(Some(value).filter(|v| *v > 0.0)?),
+            1 | 2 => None,
+            3 => {
+                *skipped.entry(self.name.clone()).or_default() += 1;
+                return None;
+            }
+            _ => return None,
+        };
+        Some(ImportRecord {
+            source,
+            name: Some(self.name.clone()),
+            check_in: Some(When::Day(day)),
+            amount,
+            ..ImportRecord::default()
+        })
+    }
+}
+
+/// Reads the unzipped CSV export (its folder, or `Habits.csv` or
+/// `Checkmarks.csv` in it) or a `.db` backup.
+pub fn read(path: &Path) -> Result<ImportBatch> {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("db"))
+    {
+        return read_backup(path);
+    }
+    let dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    };
+    let habits = read_habits(&dir.join("Habits.csv"))?;
+    let mut batch = ImportBatch::default();
+    for habit in &habits {
+        let record = habit.record(&mut batch)?;
+        batch.records.push(record);
+    }
+
+    // the wide Checkmarks.csv has a date column, then one column per habit
+    let path = dir.join("Checkmarks.csv");
+    let name = path.display().to_string();
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(open(&path)?);
+    let mut rows = reader.records();
+    let header = rows.next().transpose()?.unwrap_or_default();
+    let mut columns = Vec::new();
+    for (i, title) in header.iter().enumerate().skip(1) {
+        match habits.iter().find(|h| h.name == title) {
+            Some(habit) => columns.push((i, habit)),
+            None if title.is_empty() => {}
+            None => batch.unmapped.push(format!(
+                "{}: column '{}' is not in Habits.csv; ignored",
+                name, title
+            )),
+        }
+    }
+    let mut skipped = BTreeMap::new();
+    for row in rows {
+        let row = row?;
+        let source = format!("{}:{}", name, row.position().map_or(0, |p| p.line()));
+        let day = cell(&row, 0, &source, |v| v.parse::<NaiveDate>().ok())?;
+        for (i, habit) in &columns {
+            let Some(mut value) = cell(&row, *i, &source, |v| v.parse::<f64>().ok())? else {
+                continue;
+            };
+            // the export marks days Loop implied from the frequency with 1
+            if !habit.numerical && value == 1.0 {
+                value = 0.0;
+            }
+            let day = day.ok_or_else(|| {
+                HabitError::InvalidImport(format!("{}: no date for the entries", source))
+            })?;
+            batch
+                .records
+                .extend(habit.check_in(day, value, source.clone(), &mut skipped));
+        }
+    }
+    report_skipped(&mut batch, skipped);
+    Ok(batch)
+}
+
+fn read_habits(path: &Path) -> Result<Vec<LoopHabit>> {
+    let name = path.display().to_string();
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(open(path)?);
+    let headers = reader.headers()?.clone();
+    // older exports call the frequency NumRepetitions and Interval
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)))
+    };
+    let name_col = column(&["Name"]).ok_or_else(|| {
+        HabitError::InvalidImport(format!(
+            "{}: no Name column; is this Loop's Habits.csv?",
+            name
+        ))
+    })?;
+    let (description, question) = (column(&["Description"]), column(&["Question"]));
+    let num = column(&["FrequencyNumerator", "NumRepetitions"]);
+    let den = column(&["FrequencyDenominator", "Interval"]);
+    let (kind, target_type) = (column(&["Type"]), column(&["Target Type"]));
+    let (target, unit) = (column(&["Target Value"]), column(&["Unit"]));
+    let archived = column(&["Archived?", "Archived"]);
+
+    let mut habits = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let source = format!("{}:{}", name, row.position().map_or(0, |p| p.line()));
+        let text = |col: Option<usize>| {
+            col.and_then(|i| row.get(i))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let number = |col: Option<usize>| match col {
+            Some(i) => cell(&row, i, &source, |v| v.parse::<u32>().ok()),
+            None => Ok(None),
+        };
+        let Some(habit) = text(Some(name_col)) else {
+            return Err(HabitError::InvalidImport(format!(
+                "{}: no habit name",
+                source
+            )));
+        };
+        habits.push(LoopHabit {
+            name: habit,
+            description: text(description).or_else(|| text(question)),
+            num: number(num)?.unwrap_or(1),
+            den: number(den)?.unwrap_or(1),
+            numerical: text(kind).is_some_and(|k| k == "NUMERICAL" || k == "1"),
+            at_most: text(target_type).is_some_and(|t| t == "AT_MOST" || t == "1"),
+            target: match target {
+                Some(i) => cell(&row, i, &source, |v| v.parse::<f64>().ok())?,
+                None => None,
+            },
+            unit: text(unit),
+            archived: text(archived)
+                .is_some_and(|a| matches!(a.to_ascii_lowercase().as_str(), "true" | "1" | "yes")),
+            source,
+        });
+    }
+    Ok(habits)
+}
+
+/// The parsed cell at `i`, `None` when empty.
+fn cell<T>(
+    row: &StringRecord,
+    i: usize,
+    source: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<Option<T>> {
+    match row.get(i).filter(|v| !v.is_empty()) {
+        Some(v) => parse(v)
+            .map(Some)
+            .ok_or_else(|| HabitError::InvalidImport(format!("{}: invalid value {:?}", source, v))),
+        None => Ok(None),
+    }
+}
+
+fn report_skipped(batch: &mut ImportBatch, skipped: BTreeMap<String, u32>) {
+    for (habit, days) in skipped {
+        batch.unmapped.push(format!(
+            "'{}': {} skipped day(s) imported as not done",
+            habit, days
+        ));
+    }
+}
+
+/// Loop's backup is an SQLite database with `Habits` and `Repetitions`
+/// tables; repetitions are stamped at UTC midnight in milliseconds and
+/// numerical amounts are stored times 1000.
+#[cfg(feature = "sqlite")]
+fn read_backup(path: &Path) -> Result<ImportBatch> {
+    use rusqlite::{Connection, OpenFlags};
+
+    let name = path.display().to_string();
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut batch = ImportBatch::default();
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, question, freq_num, freq_den, archived, type,
+                target_type, target_value, unit
+         FROM Habits ORDER BY position, id",
+    )?;
+    let habits: Vec<(i64, LoopHabit)> = stmt
+        .query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            Ok((
+                id,
+                LoopHabit {
+                    source: format!("{}: habit {}", name, id),
+                    name: row.get(1)?,
+                    description: row
+                        .get::<_, Option<String>>(2)?
+                        .filter(|d| !d.is_empty())
+                        .or(row.get::<_, Option<String>>(3)?.filter(|q| !q.is_empty())),
+                    num: row.get::<_, Option<u32>>(4)?.unwrap_or(1),
+                    den: row.get::<_, Option<u32>>(5)?.unwrap_or(1),
+                    archived: row.get::<_, Option<i64>>(6)?.unwrap_or(0) != 0,
+                    numerical: row.get::<_, Option<i64>>(7)?.unwrap_or(0) == 1,
+                    at_most: row.get::<_, Option<i64>>(8)?.unwrap_or(0) == 1,
+                    target: row.get(9)?,
+                    unit: row.get::<_, Option<String>>(10)?.filter(|u| !u.is_empty()),
+                },
+            ))
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    for (_, habit) in &habits {
+        let record = habit.record(&mut batch)?;
+        batch.records.push(record);
+    }
+
+    let mut stmt =
+        conn.prepare("SELECT habit, timestamp, value FROM Repetitions ORDER BY timestamp")?;
+    let mut rows = stmt.query([])?;
+    let mut skipped = BTreeMap::new();
+    while let Some(row) = rows.next()? {
+        let (id, timestamp, value): (i64, i64, i64) = (row.get(0)?, row.get(1)?, row.get(2)?);
+        let Some((_, habit)) = habits.iter().find(|(h, _)| *h == id) else {
+            continue;
+        };
+        let day = chrono::DateTime::from_timestamp_millis(timestamp)
+            .map(|at| at.date_naive())
+            .ok_or_else(|| {
+                HabitError::InvalidImport(format!("{}: invalid timestamp {}", name, timestamp))
+            })?;
+        let amount = if habit.numerical {
+            value as f64 / 1000.0
+        } else {
+            value as f64
+        };
+        let source = format!("{}: repetition of habit {}", name, id);
+        batch
+            .records
+            .extend(habit.check_in(day, amount, source, &mut skipped));
+    }
+    report_skipped(&mut batch, skipped);
+    Ok(batch)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn read_backup(path: &Path) -> Result<ImportBatch> {
+    Err(HabitError::BackendUnavailable(format!(
+        "{}: reading Loop backups needs the sqlite feature",
+        path.display()
+    )))
+}
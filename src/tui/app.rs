@@ -0,0 +1,394 @@
+use crate::error::{HabitError, Result};
+use crate::models::habit::Habit;
+use crate::models::habit::{day_instant, goal_from};
+use crate::models::schedule::Schedule;
+use crate::models::timezone::Zone;
+use crate::storage::journal::Journal;
+use crate::storage::json_storage::HabitStore;
+use crate::storage::repository::HabitRepository;
+use chrono::{Days, Months, NaiveDate};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mode {
+    Normal,
+    Filter,
+    Form(Form),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Description,
+    Schedule,
+    Target,
+    Unit,
+    Tags,
+    Active,
+}
+
+impl Field {
+    pub const ALL: [Field; 7] = [
+        Field::Name,
+        Field::Description,
+        Field::Schedule,
+        Field::Target,
+        Field::Unit,
+        Field::Tags,
+        Field::Active,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Field::Name => "Name",
+            Field::Description => "Description",
+            Field::Schedule => "Schedule",
+            Field::Target => "Daily target",
+            Field::Unit => "Unit",
+            Field::Tags => "Tags",
+            Field::Active => "Active",
+        }
+    }
+}
+
+/// The add/edit popup. Every field is edited as text and parsed on save.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    /// The habit being edited, or `None` for a new one.
+    pub habit: Option<Uuid>,
+    pub values: Vec<String>,
+    pub focus: usize,
+}
+
+impl Form {
+    fn new() -> Self {
+        let mut values = vec![String::new(); Field::ALL.len()];
+        values[2] = "daily".into();
+        values[6] = "yes".into();
+        Self {
+            habit: None,
+            values,
+            focus: 0,
+        }
+    }
+
+    fn edit(habit: &Habit) -> Self {
+        let goal = habit.goal.as_ref();
+        Self {
+            habit: Some(habit.id),
+            values: vec![
+                habit.name.clone(),
+                habit.description.clone().unwrap_or_default(),
+                habit.schedule.to_string(),
+                goal.map(|g| g.target.to_string()).unwrap_or_default(),
+                goal.map(|g| g.unit.clone()).unwrap_or_default(),
+                habit.tags.join(" "),
+                if habit.is_active { "yes" } else { "no" }.into(),
+            ],
+            focus: 0,
+        }
+    }
+
+    pub fn value(&self, field: Field) -> &str {
+        let i = Field::ALL.iter().position(|f| *f == field).unwrap_or(0);
+        self.values[i].trim()
+    }
+
+    /// Writes the form into `habit`, checking every field first.
+    fn apply(&self, habit: &mut Habit) -> Result<()> {
+        let name = self.value(Field::Name);
+        if name.is_empty() {
+            return Err(HabitError::InvalidName(name.into()));
+        }
+        let schedule: Schedule = self.value(Field::Schedule).parse()?;
+        let goal = match self.value(Field::Target) {
+            "" => None,
+            t => {
+                let target = t.parse().map_err(|_| HabitError::InvalidAmount(t.into()))?;
+                Some(goal_from(target, self.value(Field::Unit).into())?)
+            }
+        };
+        let tags: Vec<String> = self
+            .value(Field::Tags)
+            .split([' ', ','])
+            .filter(|t| !t.is_empty())
+            .map(String::from)
+            .collect();
+        let description = self.value(Field::Description);
+
+        habit.name = name.into();
+        habit.description = (!description.is_empty()).then(|| description.into());
+        habit.schedule = schedule;
+        habit.goal = goal;
+        habit.is_active = self.value(Field::Active) == "yes";
+        habit.tags.clear();
+        habit.add_tags(tags)
+    }
+}
+
+/// Everything the TUI shows, plus the repository its changes go through.
+pub struct App<'a> {
+    repo: &'a mut dyn HabitRepository,
+    journal: Option<Journal>,
+    pub store: HabitStore,
+    pub zone: Zone,
+    pub today: NaiveDate,
+    /// Index into [`App::visible`].
+    pub selected: usize,
+    /// The calendar cursor.
+    pub day: NaiveDate,
+    pub filter: String,
+    pub show_inactive: bool,
+    pub mode: Mode,
+    pub status: String,
+    pub quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(repo: &'a mut dyn HabitRepository, zone: Zone, today: NaiveDate) -> Result<Self> {
+        let store = repo.load()?;
+        let journal = repo.location().map(Journal::for_store);
+        Ok(Self {
+            repo,
+            journal,
+            store,
+            zone,
+            today,
+            selected: 0,
+            day: today,
+            filter: String::new(),
+            show_inactive: false,
+            mode: Mode::Normal,
+            status: String::new(),
+            quit: false,
+        })
+    }
+
+    /// Habits shown in the list, after the active and text filters.
+    pub fn visible(&self) -> Vec<&Habit> {
+        let filter = self.filter.to_lowercase();
+        self.store
+            .habits
+            .iter()
+            .filter(|h| self.show_inactive || h.is_active)
+            .filter(|h| {
+                filter.is_empty()
+                    || h.name.to_lowercase().contains(&filter)
+                    || h.has_tag(&filter)
+                    || h.description
+                        .as_ref()
+                        .is_some_and(|d| d.to_lowercase().contains(&filter))
+            })
+            .collect()
+    }
+
+    pub fn current(&self) -> Option<&Habit> {
+        self.visible().get(self.selected).copied()
+    }
+
+    pub fn handle(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match self.mode.clone() {
+            Mode::Normal => self.normal_key(key.code),
+            Mode::Filter => self.filter_key(key.code),
+            Mode::Form(form) => self.form_key(form, key.code),
+        }
+    }
+
+    fn normal_key(&mut self, code: KeyCode) {
+        self.status.clear();
+        let count = self.visible().len();
+        match code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Esc if !self.filter.is_empty() => self.set_filter(String::new()),
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(count.saturating_sub(1))
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Left | KeyCode::Char('h') => self.day = self.day - Days::new(1),
+            KeyCode::Right | KeyCode::Char('l') => self.day = self.day + Days::new(1),
+            KeyCode::Char('[') => self.day = self.day - Months::new(1),
+            KeyCode::Char(']') => self.day = self.day + Months::new(1),
+            KeyCode::Char('t') => self.day = self.today,
+            KeyCode::Char(' ') | KeyCode::Enter => self.toggle_day(),
+            KeyCode::Char('e') => {
+                if let Some(habit) = self.current() {
+                    self.mode = Mode::Form(Form::edit(habit));
+                }
+            }
+            KeyCode::Char('n') => self.mode = Mode::Form(Form::new()),
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('a') => {
+                self.show_inactive = !self.show_inactive;
+                self.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn filter_key(&mut self, code: KeyCode) {
+        let mut filter = self.filter.clone();
+        match code {
+            KeyCode::Enter => self.mode = Mode::Normal,
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                filter.clear();
+            }
+            KeyCode::Backspace => {
+                filter.pop();
+            }
+            KeyCode::Char(c) => filter.push(c),
+            _ => {}
+        }
+        self.set_filter(filter);
+    }
+
+    fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.selected = 0;
+        }
+    }
+
+    fn form_key(&mut self, mut form: Form, code: KeyCode) {
+        match code {
+            KeyCode::Esc => {
+                self.mode = Mode::Normal;
+                return;
+            }
+            KeyCode::Enter => {
+                self.save_form(&form);
+                return;
+            }
+            KeyCode::Tab | KeyCode::Down => form.focus = (form.focus + 1) % Field::ALL.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.focus = (form.focus + Field::ALL.len() - 1) % Field::ALL.len()
+            }
+            // active is a yes/no switch rather than free text
+            KeyCode::Char(' ') | KeyCode::Backspace if Field::ALL[form.focus] == Field::Active => {
+                let value = if form.values[form.focus] == "yes" {
+                    "no"
+                } else {
+                    "yes"
+                };
+                form.values[form.focus] = value.into();
+            }
+            KeyCode::Char(_) if Field::ALL[form.focus] == Field::Active => {}
+            KeyCode::Backspace => {
+                form.values[form.focus].pop();
+            }
+            KeyCode::Char(c) => form.values[form.focus].push(c),
+            _ => {}
+        }
+        self.mode = Mode::Form(form);
+    }
+
+    fn save_form(&mut self, form: &Form) {
+        let result = match form.habit {
+            Some(id) => {
+                let name = self
+                    .store
+                    .habits
+                    .iter()
+                    .find(|h| h.id == id)
+                    .map_or(String::new(), |h| h.name.clone());
+                self.change(&format!("tui: edit {}", name), |store| {
+                    let habit = find(store, id)?;
+                    form.apply(habit)
+                })
+            }
+            None => {
+                let mut habit = Habit::new(String::new(), None, Schedule::default());
+                let id = habit.id;
+                form.apply(&mut habit).and_then(|()| {
+                    let name = habit.name.clone();
+                    self.change(&format!("tui: add {}", name), |store| {
+                        store.habits.push(habit.clone());
+                        Ok(())
+                    })?;
+                    // show the new habit even when a filter would hide it
+                    self.filter.clear();
+                    self.show_inactive |= !habit.is_active;
+                    self.selected = self.visible().iter().position(|h| h.id == id).unwrap_or(0);
+                    Ok(())
+                })
+            }
+        };
+        match result {
+            Ok(()) => {
+                self.status = format!("Saved '{}'", form.value(Field::Name));
+                self.mode = Mode::Normal;
+            }
+            // keep the form open so the typo can be fixed
+            Err(err) => self.status = err.to_string(),
+        }
+    }
+
+    /// Completes the selected habit on the cursor day, or clears the day if
+    /// it is already complete.
+    fn toggle_day(&mut self) {
+        let Some(habit) = self.current() else {
+            return;
+        };
+        let (id, name, day, zone, today) = (
+            habit.id,
+            habit.name.clone(),
+            self.day,
+            self.zone,
+            self.today,
+        );
+        let done = habit.completed_days(&zone).contains(&day);
+        if !done && let Err(err) = habit.check_loggable(day, today, &zone, false) {
+            self.status = err.to_string();
+            return;
+        }
+        let verb = if done { "uncomplete" } else { "complete" };
+        let result = self.change(&format!("tui: {} {} {}", verb, name, day), |store| {
+            let habit = find(store, id)?;
+            if done {
+                habit.unmark_complete(zone.midday(day), &zone);
+            } else {
+                habit.mark_complete(day_instant(day, today, &zone), &zone);
+            }
+            Ok(())
+        });
+        self.status = match result {
+            Ok(()) if done => format!("Cleared '{}' on {}", name, day),
+            Ok(()) => format!("Completed '{}' on {}", name, day),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    /// Applies `f` to a freshly loaded store under the store lock, saves it
+    /// and journals the change, just like a CLI command.
+    fn change(
+        &mut self,
+        command: &str,
+        f: impl FnOnce(&mut HabitStore) -> Result<()>,
+    ) -> Result<()> {
+        let _lock = self.repo.lock()?;
+        let before = self.repo.load()?;
+        let mut after = before.clone();
+        f(&mut after)?;
+        self.repo.save(&after)?;
+        if let Some(journal) = &self.journal {
+            journal.record(command, &before, &after)?;
+        }
+        self.store = after;
+        self.selected = self.selected.min(self.visible().len().saturating_sub(1));
+        Ok(())
+    }
+}
+
+fn find(store: &mut HabitStore, id: Uuid) -> Result<&mut Habit> {
+    store
+        .habits
+        .iter_mut()
+        .find(|h| h.id == id)
+        .ok_or_else(|| HabitError::NotFound(id.to_string()))
+}
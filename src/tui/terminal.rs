@@ -0,0 +1,26 @@
+use crate::error::Result;
+use crate::models::timezone::Zone;
+use crate::storage::repository::HabitRepository;
+use crate::tui::app::App;
+use crate::tui::ui;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+
+/// Runs the full-screen interface until the user quits. Every change is
+/// saved and journaled as it is made.
+pub fn run(repo: &mut dyn HabitRepository, zone: Zone) -> Result<()> {
+    let mut app = App::new(repo, zone, zone.today())?;
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<()> {
+        while !app.quit {
+            terminal.draw(|frame| ui::draw(frame, &app))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                app.handle(key);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
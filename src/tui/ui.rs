@@ -0,0 +1,288 @@
+use crate::models::habit::Habit;
+use crate::models::schedule::PeriodKind;
+use crate::tui::app::{App, Field, Form, Mode};
+use chrono::{Datelike, Days, NaiveDate};
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+
+const HELP: &str = "↑↓ habit  ←→ day  [ ] month  t today  space toggle  e edit  n new  / filter  a inactive  q quit";
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(frame.area());
+    let [list, right] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+    let [calendar, stats] =
+        Layout::vertical([Constraint::Length(10), Constraint::Min(0)]).areas(right);
+
+    draw_list(frame, app, list);
+    match app.current() {
+        Some(habit) => {
+            draw_calendar(frame, app, habit, calendar);
+            draw_stats(frame, app, habit, stats);
+        }
+        None => frame.render_widget(
+            Paragraph::new("No habits to show. Press n to add one.")
+                .block(Block::default().borders(Borders::ALL)),
+            right,
+        ),
+    }
+    draw_footer(frame, app, footer);
+    if let Mode::Form(form) = &app.mode {
+        draw_form(frame, form, frame.area());
+    }
+}
+
+fn draw_list(frame: &mut Frame, app: &App, area: Rect) {
+    let habits = app.visible();
+    let done = habits
+        .iter()
+        .filter(|h| h.completed_days(&app.zone).contains(&app.today))
+        .count();
+    let items: Vec<ListItem> = habits
+        .iter()
+        .map(|h| {
+            let streak = h.streaks(app.today, &app.zone);
+            let mark = if h.completed_days(&app.zone).contains(&app.today) {
+                "[x]"
+            } else if h.is_due(app.today, &app.zone) {
+                "[ ]"
+            } else {
+                " - "
+            };
+            let mut style = Style::default();
+            if !h.is_active {
+                style = style.fg(Color::DarkGray);
+            }
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ", mark)),
+                Span::styled(h.name.clone(), style),
+                Span::styled(
+                    format!("  {} {}", streak.current, streak.unit.label(streak.current)),
+                    Style::default().fg(if streak.at_risk {
+                        Color::Yellow
+                    } else {
+                        Color::Gray
+                    }),
+                ),
+            ]))
+        })
+        .collect();
+    let mut title = format!(" Habits {}/{} done today ", done, habits.len());
+    if !app.filter.is_empty() {
+        title = format!(" Habits matching '{}' ", app.filter);
+    }
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+/// A month grid with the cursor day reversed, today underlined, completed
+/// days green and marked `*`, partly logged days yellow and marked `+`.
+fn draw_calendar(frame: &mut Frame, app: &App, habit: &Habit, area: Rect) {
+    let zone = &app.zone;
+    let completed = habit.completed_days(zone);
+    let totals = habit.day_totals(zone);
+    let created = habit.created_day(zone);
+    let first = PeriodKind::Month.start_of(app.day);
+    let last = PeriodKind::Month.end_of(app.day);
+
+    let mut lines = vec![Line::from(
+        ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+            .iter()
+            .map(|d| {
+                Span::styled(
+                    format!("{:>3} ", d),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )];
+    let mut week: Vec<Span> = (0..first.weekday().num_days_from_monday())
+        .map(|_| Span::raw("    "))
+        .collect();
+    for day in first.iter_days().take_while(|d| *d <= last) {
+        let (marker, mut style) = if completed.contains(&day) {
+            (
+                '*',
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else if totals.contains_key(&day) {
+            ('+', Style::default().fg(Color::Yellow))
+        } else if day > app.today || day < created || !habit.is_scheduled(day, zone) {
+            (' ', Style::default().fg(Color::DarkGray))
+        } else {
+            (' ', Style::default())
+        };
+        if day == app.today {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if day == app.day {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        week.push(Span::styled(format!("{:>3}{}", day.day(), marker), style));
+        if day.weekday().num_days_from_monday() == 6 {
+            lines.push(Line::from(std::mem::take(&mut week)));
+        }
+    }
+    if !week.is_empty() {
+        lines.push(Line::from(week));
+    }
+    let title = format!(" {} — {} ", habit.name, app.day.format("%B %Y"));
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+        area,
+    );
+}
+
+fn draw_stats(frame: &mut Frame, app: &App, habit: &Habit, area: Rect) {
+    let zone = &app.zone;
+    let streak = habit.streaks(app.today, zone);
+    let progress = habit.progress(app.today, zone);
+    let mut lines = vec![
+        Line::from(format!("Schedule      {}", habit.schedule)),
+        Line::from(format!(
+            "Streak        {} {} (longest {}){}",
+            streak.current,
+            streak.unit.label(streak.current),
+            streak.longest,
+            if streak.at_risk {
+                ", at risk today"
+            } else {
+                ""
+            }
+        )),
+        Line::from(format!(
+            "This {:<9}{}/{} done",
+            progress.period.label(1),
+            progress.done,
+            progress.target
+        )),
+        Line::from(format!(
+            "Rate          {}",
+            habit
+                .completion_rate(app.today, zone)
+                .map_or("-".into(), |r| format!("{:.0}%", r * 100.0))
+        )),
+        Line::from(format!(
+            "Completed     {} days",
+            habit.completed_days(zone).len()
+        )),
+        Line::from(format!("Created       {}", habit.created_day(zone))),
+    ];
+    if let Some(goal) = &habit.goal {
+        lines.push(Line::from(format!(
+            "{:<14}{}/{} {}",
+            cursor_label(app.day, app.today),
+            habit.day_total(app.day, zone),
+            goal.target,
+            goal.unit
+        )));
+    }
+    if !habit.tags.is_empty() {
+        lines.push(Line::from(format!(
+            "Tags          {}",
+            habit.tags.join(", ")
+        )));
+    }
+    if let Some(desc) = &habit.description {
+        lines.push(Line::from(format!("Notes         {}", desc)));
+    }
+    if !habit.is_active {
+        lines.push(Line::styled(
+            "Inactive",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Stats ")),
+        area,
+    );
+}
+
+fn cursor_label(day: NaiveDate, today: NaiveDate) -> String {
+    if day == today {
+        "Today".into()
+    } else if day + Days::new(1) == today {
+        "Yesterday".into()
+    } else {
+        day.format("%b %-d").to_string()
+    }
+}
+
+fn draw_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let first = match &app.mode {
+        Mode::Filter => Line::from(format!("Filter: {}_", app.filter)),
+        _ if !app.status.is_empty() => {
+            Line::styled(app.status.clone(), Style::default().fg(Color::Yellow))
+        }
+        _ => Line::from(format!(
+            "{}  ·  cursor {}",
+            app.today.format("%a %Y-%m-%d"),
+            app.day
+        )),
+    };
+    let help = match app.mode {
+        Mode::Normal => HELP,
+        Mode::Filter => "type to filter by name, tag or description  Enter keep  Esc clear",
+        Mode::Form(_) => "Tab/↑↓ field  space toggles Active  Enter save  Esc cancel",
+    };
+    frame.render_widget(
+        Paragraph::new(vec![
+            first,
+            Line::styled(help, Style::default().fg(Color::DarkGray)),
+        ]),
+        area,
+    );
+}
+
+fn draw_form(frame: &mut Frame, form: &Form, area: Rect) {
+    let width = area.width.min(60);
+    let height = (Field::ALL.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let lines: Vec<Line> = Field::ALL
+        .iter()
+        .zip(&form.values)
+        .enumerate()
+        .map(|(i, (field, value))| {
+            let focused = i == form.focus;
+            let value = if focused {
+                format!("{}_", value)
+            } else {
+                value.clone()
+            };
+            let style = if focused {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::raw(format!("{:<13}", field.label())),
+                Span::styled(value, style),
+            ])
+        })
+        .collect();
+    let title = if form.habit.is_some() {
+        " Edit habit "
+    } else {
+        " New habit "
+    };
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+        popup,
+    );
+}
@@ -0,0 +1,35 @@
+use crate::error::{HabitError, Result};
+use chrono::{Days, NaiveDate};
+
+/// Parses a day given as `YYYY-MM-DD`, `today`, `yesterday` or a relative
+/// offset into the past such as `-2d` or `-1w`.
+pub fn parse_day(input: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let s = input.trim().to_lowercase();
+    let invalid = || HabitError::InvalidDate(input.to_string());
+    match s.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return Ok(today - Days::new(1)),
+        _ => {}
+    }
+    if let Some(rel) = s.strip_prefix('-') {
+        let (n, days_per_unit) = if let Some(n) = rel.strip_suffix('d') {
+            (n, 1)
+        } else if let Some(n) = rel.strip_suffix('w') {
+            (n, 7)
+        } else {
+            return Err(invalid());
+        };
+        let n: u64 = n.parse().map_err(|_| invalid())?;
+        let days = n.checked_mul(days_per_unit).ok_or_else(invalid)?;
+        return today.checked_sub_days(Days::new(days)).ok_or_else(invalid);
+    }
+    NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(|_| invalid())
+}
+
+/// Every day from `from` to `to`, inclusive.
+pub fn day_range(from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>> {
+    if from > to {
+        return Err(HabitError::InvalidDate(format!("{} is after {}", from, to)));
+    }
+    Ok(from.iter_days().take_while(|d| *d <= to).collect())
+}
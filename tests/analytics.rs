@@ -0,0 +1,138 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use habit::cli::report::{self, Period};
+use habit::models::analytics::{Direction, Gap, HabitStats};
+use habit::models::habit::{Habit, Pause};
+use habit::models::schedule::{PeriodKind, Schedule};
+use habit::models::timezone::Zone;
+use habit::storage::json_storage::HabitStore;
+use std::fs;
+
+fn day(d: &str) -> NaiveDate {
+    d.parse().unwrap()
+}
+
+fn fixture() -> HabitStore {
+    HabitStore::from_slice(&fs::read("tests/fixtures/output_store.json").unwrap()).unwrap()
+}
+
+#[test]
+fn fixture_stats() {
+    let store = fixture();
+    let stats: Vec<HabitStats> = store.habits[..2]
+        .iter()
+        .map(|h| HabitStats::for_habit(h, day("2025-01-12"), &Zone::default()))
+        .collect();
+    insta::assert_json_snapshot!(stats);
+}
+
+#[test]
+fn trend_compares_with_the_previous_30_days() {
+    let zone = Zone::default();
+    let today = day("2025-03-01");
+    let mut habit = Habit::new("Run".into(), None, Schedule::Daily);
+    habit.created_at = zone.midday(day("2024-12-01"));
+    // every day of the earlier window, every other day of the recent one
+    for (i, d) in day("2024-12-31").iter_days().take(61).enumerate() {
+        if i < 30 || i % 2 == 0 {
+            habit.mark_complete(zone.midday(d), &zone);
+        }
+    }
+    let trend = HabitStats::for_habit(&habit, today, &zone).trend.unwrap();
+    assert_eq!(trend.direction, Direction::Declining);
+    assert_eq!(trend.previous, 1.0);
+    assert_eq!(trend.current, 0.5);
+}
+
+#[test]
+fn average_time_wraps_around_midnight() {
+    let zone = Zone::default();
+    let mut habit = Habit::new("Sleep".into(), None, Schedule::Daily);
+    habit.created_at = zone.midday(day("2025-01-01"));
+    habit.mark_complete(Utc.with_ymd_and_hms(2025, 1, 1, 23, 30, 0).unwrap(), &zone);
+    habit.mark_complete(Utc.with_ymd_and_hms(2025, 1, 3, 0, 30, 0).unwrap(), &zone);
+    // backdated at midday, so left out
+    habit.mark_complete(zone.midday(day("2025-01-04")), &zone);
+
+    let stats = HabitStats::for_habit(&habit, day("2025-01-10"), &zone);
+    assert_eq!(stats.average_time, NaiveTime::from_hms_opt(0, 0, 0));
+    assert_eq!(
+        stats.longest_gap,
+        Some(Gap {
+            from: day("2025-01-05"),
+            to: day("2025-01-09"),
+            days: 5
+        })
+    );
+}
+
+#[test]
+fn paused_days_are_neither_missed_nor_done() {
+    let zone = Zone::default();
+    let today = day("2025-01-12");
+    let mut habit = Habit::new("Read".into(), None, Schedule::Daily);
+    habit.created_at = zone.midday(day("2025-01-01"));
+    for d in day("2025-01-01").iter_days().take(12) {
+        // nothing logged while away but one check-in on the 8th
+        if !(day("2025-01-06")..=day("2025-01-10")).contains(&d) || d == day("2025-01-08") {
+            habit.mark_complete(zone.midday(d), &zone);
+        }
+    }
+    habit.pauses.push(Pause {
+        from: day("2025-01-06"),
+        until: Some(day("2025-01-10")),
+        vacation: false,
+    });
+
+    let streak = habit.streaks(today, &zone);
+    assert_eq!((streak.current, streak.longest), (7, 7));
+    assert_eq!(
+        habit.schedule_counts(day("2025-01-01"), today, today, &zone),
+        (7, 7)
+    );
+    assert!(!habit.is_due(day("2025-01-09"), &zone));
+    assert_eq!(
+        HabitStats::for_habit(&habit, today, &zone).longest_gap,
+        None
+    );
+
+    // a 3/week habit away Monday to Thursday owes one of its three that week
+    habit.schedule = Schedule::TimesPerWeek(3);
+    assert_eq!(
+        habit.quota_in(PeriodKind::Week, day("2025-01-08"), &zone),
+        1
+    );
+    assert_eq!(
+        habit.quota_in(PeriodKind::Week, day("2025-01-15"), &zone),
+        3
+    );
+
+    assert!(habit.resume(day("2025-01-08")));
+    assert_eq!(habit.pauses[0].until, Some(day("2025-01-07")));
+    assert!(!habit.resume(day("2025-01-08")));
+}
+
+#[test]
+fn monthly_reports_split_a_week_across_the_month_boundary() {
+    let zone = Zone::default();
+    let today = day("2025-03-10");
+    let mut habit = Habit::new("Swim".into(), None, Schedule::TimesPerWeek(3));
+    habit.created_at = zone.midday(day("2024-12-01"));
+    // Monday 27 January to Sunday 2 February
+    for d in ["2025-01-28", "2025-01-30", "2025-02-01"] {
+        habit.mark_complete(zone.midday(day(d)), &zone);
+    }
+    let habits = [habit];
+    let month = |m| {
+        let period = Period::month(Some(m), today).unwrap();
+        let row = &report::rows(&habits, period, today, &zone)[0];
+        (row.completed, row.scheduled)
+    };
+    let (jan, feb) = (month("2025-01"), month("2025-02"));
+    assert_eq!(jan.0 + feb.0, 3);
+    // the split week's quota of three is shared, not asked for twice
+    assert_eq!((jan.0, feb.0), (2, 1));
+    assert_eq!(
+        (jan.0 + feb.0, jan.1 + feb.1),
+        habits[0].schedule_counts_within(day("2025-01-01"), day("2025-02-28"), today, &zone)
+    );
+}
@@ -0,0 +1,144 @@
+use habit::models::timezone::Zone;
+use habit::storage::json_storage::HabitStore;
+use habit::transfer::import::{self, ImportSummary, MergeBy, Source};
+use serde_json::{Value, json};
+use std::path::Path;
+
+/// Imports a fixture into an empty store.
+fn import(source: Source, fixture: &str) -> (HabitStore, ImportSummary, Vec<String>) {
+    let path = Path::new("tests/fixtures/import").join(fixture);
+    let batch = import::read(source, &path, &[]).unwrap();
+    let mut store = HabitStore::default();
+    let summary =
+        import::merge(&mut store, &batch.records, MergeBy::Name, &Zone::default()).unwrap();
+    (store, summary, batch.unmapped)
+}
+
+/// What each habit ended up as, in a form that is easy to snapshot.
+fn habits(store: &HabitStore) -> Value {
+    let zone = Zone::default();
+    store
+        .habits
+        .iter()
+        .map(|h| {
+            json!({
+                "name": h.name,
+                "description": h.description,
+                "schedule": h.schedule.to_string(),
+                "goal": h.goal.as_ref().map(|g| format!("{} {}", g.target, g.unit)),
+                "tags": h.tags,
+                "active": h.is_active,
+                "created": h.created_day(&zone),
+                "days": h.day_totals(&zone),
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn loop_csv_export() {
+    let (store, summary, unmapped) = import(Source::Loop, "loop");
+    insta::assert_json_snapshot!(habits(&store));
+    assert_eq!(summary.added, 13);
+    assert_eq!(
+        unmapped,
+        [
+            "'Floss': 5 times in 10 days became 4/week",
+            "'Meditate': 1 skipped day(s) imported as not done",
+        ]
+    );
+}
+
+#[test]
+fn habitica_data_export() {
+    let (store, summary, unmapped) = import(Source::Habitica, "habitica.json");
+    insta::assert_json_snapshot!(habits(&store));
+    assert_eq!(summary.created.len(), 4);
+    assert_eq!(
+        unmapped,
+        [
+            "'Late-night snack': a habit that only scores down; skipped",
+            "'Deep clean': every 2 weeks became sat",
+            "1 to-do(s) and 1 reward(s) skipped",
+        ]
+    );
+    // the source's ids are kept, so a later export merges by id
+    assert_eq!(
+        store.habits[0].id.to_string(),
+        "7c9e6679-7425-40de-944b-e07fc1f90ae7"
+    );
+}
+
+#[test]
+fn date_habit_pairs() {
+    let (mut store, summary, unmapped) = import(Source::DateHabit, "date_habit.csv");
+    assert_eq!(summary.created, ["Read", "Run"]);
+    assert_eq!((summary.added, summary.duplicates), (4, 1));
+    assert!(unmapped.is_empty());
+    assert_eq!(
+        habits(&store)[0]["days"],
+        json!({"2024-02-01": 1.0, "2024-02-02": 1.0})
+    );
+
+    let path = Path::new("tests/fixtures/import/date_habit.csv");
+    let batch = import::read(Source::DateHabit, path, &[]).unwrap();
+    let again = import::merge(&mut store, &batch.records, MergeBy::Name, &Zone::default()).unwrap();
+    assert_eq!(again.matched, ["Read", "Run"]);
+    assert_eq!((again.added, again.duplicates), (0, 5));
+}
+
+#[test]
+fn repeated_rows_count_once() {
+    let (store, summary, _) = import(Source::Csv, "repeated_rows.csv");
+    assert_eq!((summary.added, summary.duplicates), (3, 2));
+    assert_eq!(
+        habits(&store)[0]["days"],
+        json!({"2024-02-01": 5.0, "2024-02-02": 3.0})
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn loop_backup_database() {
+    let dir = std::env::temp_dir().join(format!("habit-import-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Loop Habits Backup.db");
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute_batch(
+        "CREATE TABLE Habits (id INTEGER PRIMARY KEY, archived INTEGER, color INTEGER,
+             description TEXT, freq_den INTEGER, freq_num INTEGER, name TEXT, position INTEGER,
+             type INTEGER, target_type INTEGER, target_value REAL, unit TEXT, question TEXT);
+         CREATE TABLE Repetitions (id INTEGER PRIMARY KEY, habit INTEGER, timestamp INTEGER,
+             value INTEGER);
+         INSERT INTO Habits VALUES (1, 0, 0, '', 1, 1, 'Meditate', 0, 0, 0, 0, '', 'Did you meditate?');
+         INSERT INTO Habits VALUES (2, 1, 0, 'Run', 7, 2, 'Run', 1, 1, 0, 5.0, 'km', '');
+         INSERT INTO Repetitions VALUES (1, 1, 1709251200000, 2);
+         INSERT INTO Repetitions VALUES (2, 1, 1709337600000, 3);
+         INSERT INTO Repetitions VALUES (3, 2, 1709251200000, 5500);",
+    )
+    .unwrap();
+    drop(conn);
+
+    let batch = import::read(Source::Loop, &path, &[]).unwrap();
+    let mut store = HabitStore::default();
+    import::merge(&mut store, &batch.records, MergeBy::Name, &Zone::default()).unwrap();
+    assert_eq!(
+        habits(&store),
+        json!([
+            {
+                "name": "Meditate", "description": "Did you meditate?", "schedule": "daily",
+                "goal": null, "tags": [], "active": true, "created": "2024-03-01",
+                "days": {"2024-03-01": 1.0},
+            },
+            {
+                "name": "Run", "description": "Run", "schedule": "2/week",
+                "goal": "5 km", "tags": [], "active": false, "created": "2024-03-01",
+                "days": {"2024-03-01": 5.5},
+            },
+        ])
+    );
+    assert_eq!(
+        batch.unmapped,
+        ["'Meditate': 1 skipped day(s) imported as not done"]
+    );
+}
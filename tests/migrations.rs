@@ -0,0 +1,95 @@
+use habit::error::HabitError;
+use habit::models::schedule::Schedule;
+use habit::storage::json_storage::HabitStore;
+use habit::storage::migrations::CURRENT_SCHEMA_VERSION;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Copies a fixture into a fresh directory so loading can rewrite it.
+fn stage(fixture: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("habit-migrations-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("habits.json");
+    fs::copy(Path::new("tests/fixtures").join(fixture), &path).unwrap();
+    (dir, path)
+}
+
+fn backups(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.to_string_lossy().ends_with(".bak"))
+        .collect()
+}
+
+#[test]
+fn v1_target_frequency_becomes_weekly_schedule() {
+    let (dir, path) = stage("store_v1.json");
+    let original = fs::read(&path).unwrap();
+
+    let store = HabitStore::load_from(&path).unwrap();
+
+    assert_eq!(store.schema_version, CURRENT_SCHEMA_VERSION);
+    let schedules: Vec<_> = store.habits.iter().map(|h| h.schedule.clone()).collect();
+    assert_eq!(
+        schedules,
+        [Schedule::Daily, Schedule::TimesPerWeek(3), Schedule::Daily]
+    );
+    assert_eq!(store.habits[0].completions.len(), 3);
+    assert!(!store.habits[2].is_active);
+
+    let taken = backups(&dir);
+    assert_eq!(taken.len(), 1);
+    assert!(taken[0].to_string_lossy().contains(".v1."));
+    assert_eq!(fs::read(&taken[0]).unwrap(), original);
+
+    let rewritten: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(rewritten["schema_version"], CURRENT_SCHEMA_VERSION);
+    assert!(rewritten["habits"][1].get("target_frequency").is_none());
+
+    // a second load is already current and takes no further backup
+    HabitStore::load_from(&path).unwrap();
+    assert_eq!(backups(&dir).len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unversioned_file_with_schedules_and_amounts_keeps_them() {
+    let (dir, path) = stage("store_v1_extended.json");
+
+    let store = HabitStore::load_from(&path).unwrap();
+
+    let water = &store.habits[0];
+    assert_eq!(water.schedule, "mon,wed,fri".parse().unwrap());
+    assert_eq!(water.goal.as_ref().unwrap().target, 8.0);
+    let amounts: Vec<_> = water.completions.iter().map(|c| c.amount).collect();
+    assert_eq!(amounts, [Some(3.0), Some(5.5), None]);
+    assert_eq!(store.settings.timezone.unwrap().to_string(), "Asia/Kolkata");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn current_version_loads_without_backup() {
+    let (dir, path) = stage("store_v2.json");
+    let original = fs::read(&path).unwrap();
+
+    let store = HabitStore::load_from(&path).unwrap();
+
+    assert_eq!(store.habits[0].schedule, Schedule::EveryNDays(2));
+    assert!(backups(&dir).is_empty());
+    assert_eq!(fs::read(&path).unwrap(), original);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn newer_version_is_refused_and_left_untouched() {
+    let (dir, path) = stage("store_future.json");
+    let original = fs::read(&path).unwrap();
+
+    let err = HabitStore::load_from(&path).unwrap_err();
+
+    assert!(matches!(err, HabitError::UnsupportedVersion(_)));
+    assert_eq!(fs::read(&path).unwrap(), original);
+    assert!(backups(&dir).is_empty());
+    fs::remove_dir_all(dir).unwrap();
+}
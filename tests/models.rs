@@ -0,0 +1,346 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use habit::error::HabitError;
+use habit::models::habit::{Goal, Habit};
+use habit::models::schedule::{PeriodKind, Schedule};
+use habit::models::streak::StreakRun;
+use habit::models::timezone::Zone;
+
+fn day(d: &str) -> NaiveDate {
+    d.parse().unwrap()
+}
+
+/// A habit created on `created` and completed at midday on each of `done`.
+fn habit(schedule: Schedule, created: &str, done: &[&str]) -> Habit {
+    let zone = Zone::default();
+    let mut habit = Habit::new("Read".into(), None, schedule);
+    habit.created_at = zone.midday(day(created));
+    for d in done {
+        habit.mark_complete(zone.midday(day(d)), &zone);
+    }
+    habit
+}
+
+#[test]
+fn streak_current_and_longest_are_split_by_a_gap() {
+    let habit = habit(
+        Schedule::Daily,
+        "2025-01-01",
+        &[
+            "2025-01-01",
+            "2025-01-02",
+            "2025-01-03",
+            "2025-01-05",
+            "2025-01-06",
+        ],
+    );
+    let streaks = habit.streaks(day("2025-01-06"), &Zone::default());
+    assert_eq!(streaks.unit, PeriodKind::Day);
+    assert_eq!(streaks.current, 2);
+    assert_eq!(streaks.longest, 3);
+    assert!(!streaks.at_risk);
+    assert_eq!(
+        streaks.history,
+        [
+            StreakRun {
+                start: day("2025-01-01"),
+                end: day("2025-01-03"),
+                length: 3
+            },
+            StreakRun {
+                start: day("2025-01-05"),
+                end: day("2025-01-06"),
+                length: 2
+            },
+        ]
+    );
+}
+
+#[test]
+fn days_off_the_schedule_do_not_break_a_streak() {
+    let mon_wed_fri = "mon,wed,fri".parse().unwrap();
+    // Monday 6 January to Monday 13 January
+    let habit = habit(
+        mon_wed_fri,
+        "2025-01-06",
+        &["2025-01-06", "2025-01-08", "2025-01-10", "2025-01-13"],
+    );
+    let streaks = habit.streaks(day("2025-01-13"), &Zone::default());
+    assert_eq!(streaks.current, 4);
+    assert_eq!(streaks.longest, 4);
+    assert_eq!(streaks.history.len(), 1);
+}
+
+#[test]
+fn today_not_done_yet_keeps_the_streak_at_risk() {
+    let zone = Zone::default();
+    let mut habit = habit(
+        Schedule::Daily,
+        "2025-01-01",
+        &["2025-01-01", "2025-01-02", "2025-01-03"],
+    );
+    let streaks = habit.streaks(day("2025-01-04"), &zone);
+    assert_eq!(streaks.current, 3);
+    assert!(streaks.at_risk);
+
+    habit.mark_complete(zone.midday(day("2025-01-04")), &zone);
+    let streaks = habit.streaks(day("2025-01-04"), &zone);
+    assert_eq!(streaks.current, 4);
+    assert!(!streaks.at_risk);
+
+    // a whole missed day ends it
+    let streaks = habit.streaks(day("2025-01-06"), &zone);
+    assert_eq!(streaks.current, 0);
+    assert_eq!(streaks.longest, 4);
+    assert!(!streaks.at_risk);
+}
+
+#[test]
+fn weekly_quota_is_at_risk_only_when_the_days_left_are_needed() {
+    let zone = Zone::default();
+    // three a week, met in the week of 6 January
+    let habit = habit(
+        Schedule::TimesPerWeek(3),
+        "2025-01-06",
+        &["2025-01-06", "2025-01-07", "2025-01-08", "2025-01-13"],
+    );
+    let streaks = habit.streaks(day("2025-01-15"), &zone);
+    assert_eq!(streaks.unit, PeriodKind::Week);
+    assert_eq!(streaks.current, 1);
+    assert!(!streaks.at_risk);
+    // two more needed with two days left
+    let streaks = habit.streaks(day("2025-01-18"), &zone);
+    assert_eq!(streaks.current, 1);
+    assert!(streaks.at_risk);
+}
+
+#[test]
+fn schedules_display_as_they_parse() {
+    for text in [
+        "daily",
+        "mon,wed,fri",
+        "3/week",
+        "every 2 days",
+        "4/month",
+        "days 1,15,31",
+    ] {
+        let schedule: Schedule = text.parse().unwrap();
+        assert_eq!(schedule.to_string(), text);
+        assert_eq!(schedule.to_string().parse::<Schedule>().unwrap(), schedule);
+    }
+    // aliases and untidy input come out in the canonical form
+    for (text, canonical) in [
+        ("Every Day", "daily"),
+        ("weekdays", "mon,tue,wed,thu,fri"),
+        ("weekends", "sat,sun"),
+        ("fri, mon, fri", "mon,fri"),
+        ("every 3d", "every 3 days"),
+        ("days 15,1,15", "days 1,15"),
+    ] {
+        assert_eq!(text.parse::<Schedule>().unwrap().to_string(), canonical);
+    }
+    for bad in [
+        "",
+        "0/week",
+        "8/week",
+        "32/month",
+        "every 0 days",
+        "days 0",
+        "someday",
+    ] {
+        assert!(bad.parse::<Schedule>().is_err(), "{:?} parsed", bad);
+    }
+}
+
+#[test]
+fn first_quota_period_is_prorated_from_creation() {
+    let zone = Zone::default();
+    // created on Sunday 12 January, the last day of its first week
+    let sunday = habit(
+        Schedule::TimesPerWeek(3),
+        "2025-01-12",
+        &["2025-01-13", "2025-01-14", "2025-01-15"],
+    );
+    assert_eq!(
+        sunday.quota_in(PeriodKind::Week, day("2025-01-12"), &zone),
+        0
+    );
+    assert_eq!(
+        sunday.quota_in(PeriodKind::Week, day("2025-01-13"), &zone),
+        3
+    );
+    assert_eq!(sunday.completion_rate(day("2025-01-16"), &zone), Some(1.0));
+    assert!(!sunday.is_due(day("2025-01-12"), &zone));
+    let streaks = sunday.streaks(day("2025-01-16"), &zone);
+    assert_eq!((streaks.current, streaks.longest), (1, 1));
+
+    // five of seven days left owe two of three
+    let wednesday = habit(Schedule::TimesPerWeek(3), "2025-01-08", &[]);
+    assert_eq!(
+        wednesday.quota_in(PeriodKind::Week, day("2025-01-08"), &zone),
+        2
+    );
+    assert_eq!(wednesday.progress(day("2025-01-08"), &zone).target, 2);
+
+    // created on the 31st, a 4/month habit owes nothing that month
+    let monthly = habit(Schedule::TimesPerMonth(4), "2025-01-31", &[]);
+    assert_eq!(
+        monthly.quota_in(PeriodKind::Month, day("2025-01-31"), &zone),
+        0
+    );
+    assert_eq!(monthly.completion_rate(day("2025-02-01"), &zone), None);
+}
+
+#[test]
+fn unmark_complete_on_an_empty_day_changes_nothing() {
+    let zone = Zone::default();
+    let mut habit = habit(Schedule::Daily, "2025-01-01", &["2025-01-01", "2025-01-03"]);
+    let before = habit.completions.clone();
+    assert!(!habit.unmark_complete(zone.midday(day("2025-01-02")), &zone));
+    assert_eq!(habit.completions, before);
+
+    assert!(habit.unmark_complete(zone.midday(day("2025-01-03")), &zone));
+    assert!(!habit.unmark_complete(zone.midday(day("2025-01-03")), &zone));
+    assert_eq!(habit.completed_days(&zone).len(), 1);
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+#[test]
+fn zones_parse_names_and_offsets() {
+    for (text, shown) in [
+        ("Asia/Kolkata", "Asia/Kolkata"),
+        ("America/New_York", "America/New_York"),
+        ("utc", "UTC"),
+        ("Z", "UTC"),
+        ("GMT", "UTC"),
+        ("UTC+5:30", "+05:30"),
+        ("utc+530", "+05:30"),
+        ("+05:30", "+05:30"),
+        ("-0800", "-08:00"),
+        ("GMT-3", "-03:00"),
+        (":Europe/Paris", "Europe/Paris"),
+    ] {
+        let zone: Zone = text.parse().unwrap();
+        assert_eq!(zone.to_string(), shown, "{}", text);
+        assert_eq!(shown.parse::<Zone>().unwrap(), zone);
+    }
+    for bad in [
+        "",
+        "Mars/Olympus",
+        "UTC+",
+        "+5:75",
+        "+123456",
+        "+25",
+        "UTC+five",
+    ] {
+        assert!(
+            matches!(bad.parse::<Zone>(), Err(HabitError::InvalidTimezone(_))),
+            "{:?} parsed",
+            bad
+        );
+    }
+    assert_eq!(
+        Zone::resolve(Some("Asia/Kolkata"), Some(Zone::default())).unwrap(),
+        "Asia/Kolkata".parse().unwrap()
+    );
+    assert!(Zone::resolve(Some("nowhere"), None).is_err());
+}
+
+#[test]
+fn days_change_at_local_midnight() {
+    let kolkata: Zone = "Asia/Kolkata".parse().unwrap();
+    assert_eq!(
+        kolkata.date_of(at("2025-01-01T18:29:59Z")),
+        day("2025-01-01")
+    );
+    assert_eq!(
+        kolkata.date_of(at("2025-01-01T18:30:00Z")),
+        day("2025-01-02")
+    );
+
+    // New York is five hours behind in winter and four in summer
+    let new_york: Zone = "America/New_York".parse().unwrap();
+    assert_eq!(
+        new_york.date_of(at("2025-01-02T04:59:59Z")),
+        day("2025-01-01")
+    );
+    assert_eq!(
+        new_york.date_of(at("2025-01-02T05:00:00Z")),
+        day("2025-01-02")
+    );
+    assert_eq!(
+        new_york.date_of(at("2025-07-02T03:59:59Z")),
+        day("2025-07-01")
+    );
+    assert_eq!(
+        new_york.date_of(at("2025-07-02T04:00:00Z")),
+        day("2025-07-02")
+    );
+
+    let behind: Zone = "UTC-8".parse().unwrap();
+    assert_eq!(
+        behind.date_of(at("2025-01-02T07:59:59Z")),
+        day("2025-01-01")
+    );
+    assert_eq!(behind.midday(day("2025-01-01")), at("2025-01-01T20:00:00Z"));
+    // spring forward in New York: midday is still on the day itself
+    assert_eq!(
+        new_york.midday(day("2025-03-09")),
+        at("2025-03-09T16:00:00Z")
+    );
+
+    // the same instant is one day's check-in in one zone and the next's in another
+    let zone = Zone::default();
+    let mut habit = habit(Schedule::Daily, "2025-01-01", &[]);
+    habit.mark_complete(at("2025-01-01T20:00:00Z"), &kolkata);
+    assert_eq!(
+        habit
+            .completed_days(&kolkata)
+            .into_iter()
+            .collect::<Vec<_>>(),
+        [day("2025-01-02")]
+    );
+    assert_eq!(
+        habit.completed_days(&zone).into_iter().collect::<Vec<_>>(),
+        [day("2025-01-01")]
+    );
+}
+
+#[test]
+fn partial_amounts_add_up_to_the_daily_target() {
+    let zone = Zone::default();
+    let mut water = habit(Schedule::Daily, "2025-01-01", &[]);
+    water.goal = Some(Goal {
+        target: 8.0,
+        unit: "glasses".into(),
+    });
+    assert_eq!(
+        water.log_amount(at("2025-01-02T09:00:00Z"), 3.0, &zone),
+        3.0
+    );
+    assert_eq!(
+        water.log_amount(at("2025-01-02T13:00:00Z"), 2.5, &zone),
+        5.5
+    );
+    assert!(!water.completed_days(&zone).contains(&day("2025-01-02")));
+    assert_eq!(water.day_ratio(day("2025-01-02"), &zone), 5.5 / 8.0);
+    assert!(water.is_due(day("2025-01-02"), &zone));
+
+    assert_eq!(
+        water.log_amount(at("2025-01-02T18:00:00Z"), 2.5, &zone),
+        8.0
+    );
+    assert!(water.completed_days(&zone).contains(&day("2025-01-02")));
+    assert!(!water.is_due(day("2025-01-02"), &zone));
+    // the next day starts from nothing
+    assert_eq!(water.day_total(day("2025-01-03"), &zone), 0.0);
+
+    // marking complete tops a day up to the target, and only once
+    water.log_amount(at("2025-01-03T09:00:00Z"), 2.0, &zone);
+    assert!(water.mark_complete(at("2025-01-03T20:00:00Z"), &zone));
+    assert_eq!(water.day_total(day("2025-01-03"), &zone), 8.0);
+    assert!(!water.mark_complete(at("2025-01-03T21:00:00Z"), &zone));
+    assert_eq!(water.completions.last().unwrap().amount, Some(6.0));
+}
@@ -0,0 +1,574 @@
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Copies the fixture store into a fresh directory and returns its path.
+fn stage() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("habit-output-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("habits.json");
+    fs::copy(Path::new("tests/fixtures/output_store.json"), &path).unwrap();
+    path
+}
+
+fn habit(store: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_habit"))
+        .args(["--tz", "UTC"])
+        .args(args)
+        .env("HABIT_STORAGE", store)
+        .env("HABIT_CONFIG", store.with_extension("toml"))
+        .output()
+        .unwrap()
+}
+
+fn habit_with_input(store: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_habit"))
+        .args(["--tz", "UTC"])
+        .args(args)
+        .env("HABIT_STORAGE", store)
+        .env("HABIT_CONFIG", store.with_extension("toml"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// Parses JSON output and blanks the fields that depend on when the test runs.
+fn json(output: &Output) -> Value {
+    let mut value: Value = serde_json::from_str(&stdout(output)).unwrap();
+    redact(&mut value);
+    value
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match key.as_str() {
+                    "at" | "today" | "progress" | "due_today" | "rate" => {
+                        *value = Value::from("[redacted]")
+                    }
+                    _ => redact(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Blanks the `rate` column of a habit table, the fourth from the end.
+fn redact_rate(table: &str, sep: char) -> String {
+    table
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let mut cells: Vec<&str> = line.rsplitn(5, sep).collect();
+            if i > 0 {
+                cells[3] = "[redacted]";
+            }
+            cells.reverse();
+            cells.join(&sep.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn list_json() {
+    let store = stage();
+    insta::assert_json_snapshot!(json(&habit(&store, &["list", "--format", "json"])));
+}
+
+#[test]
+fn list_csv() {
+    let store = stage();
+    let out = stdout(&habit(&store, &["list", "--format", "csv", "--active"]));
+    insta::assert_snapshot!(redact_rate(&out, ','));
+}
+
+#[test]
+fn list_tsv() {
+    let store = stage();
+    let out = stdout(&habit(&store, &["--format", "tsv", "list"]));
+    insta::assert_snapshot!(redact_rate(&out, '\t'));
+}
+
+#[test]
+fn list_table() {
+    let store = stage();
+    let out = habit(
+        &store,
+        &[
+            "list",
+            "--all",
+            "--sort",
+            "name",
+            "--columns",
+            "name,schedule,tags,created",
+        ],
+    );
+    insta::assert_snapshot!(stdout(&out));
+    let out = habit(
+        &store,
+        &[
+            "list",
+            "--tag",
+            "Learning",
+            "--search",
+            "PHONE",
+            "--columns",
+            "name",
+        ],
+    );
+    assert_eq!(stdout(&out), "NAME\nRead\n");
+}
+
+#[test]
+fn list_sorts_by_rate_with_unrated_habits_last() {
+    let store = stage();
+    assert!(habit(&store, &["add", "Fresh"]).status.success());
+    let out = json(&habit(
+        &store,
+        &["list", "--sort", "rate", "--format", "json"],
+    ));
+    let names: Vec<&str> = out
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| h["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Read", "Water", "Fresh"]);
+}
+
+#[test]
+fn streaks_json() {
+    let store = stage();
+    let out = habit(&store, &["streaks", "--history", "--format", "json"]);
+    insta::assert_json_snapshot!(json(&out));
+}
+
+#[test]
+fn add_reports_the_new_habit() {
+    let store = stage();
+    let mut added = json(&habit(
+        &store,
+        &[
+            "add", "Stretch", "--format", "json", "--target", "10", "--unit", "minutes",
+        ],
+    ));
+    let id = added["id"].take();
+    let created = added["created"].take();
+    assert!(id.as_str().unwrap().parse::<uuid::Uuid>().is_ok());
+    assert!(created.is_string());
+    insta::assert_json_snapshot!(added);
+
+    let listed = json(&habit(&store, &["list", "--format", "json"]));
+    let stretch = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|h| h["name"] == "Stretch");
+    assert_eq!(stretch.unwrap()["id"], id);
+}
+
+#[test]
+fn complete_json() {
+    let store = stage();
+    let out = habit(
+        &store,
+        &[
+            "complete",
+            "Read",
+            "--from",
+            "2025-01-04",
+            "--to",
+            "2025-01-06",
+            "--format",
+            "json",
+        ],
+    );
+    insta::assert_json_snapshot!(json(&out));
+}
+
+#[test]
+fn complete_amount_csv() {
+    let store = stage();
+    let out = habit(
+        &store,
+        &[
+            "complete",
+            "Water",
+            "--date",
+            "2025-01-02",
+            "--amount",
+            "5",
+            "--format",
+            "csv",
+        ],
+    );
+    insta::assert_snapshot!(stdout(&out));
+}
+
+#[test]
+fn doctor_json() {
+    let store = stage();
+    insta::assert_json_snapshot!(json(&habit(&store, &["doctor", "--format", "json"])));
+}
+
+#[test]
+fn doctor_fix_keeps_forced_future_completions() {
+    let store = stage();
+    let ahead = (chrono::Utc::now().date_naive() + chrono::Days::new(3)).to_string();
+    let forced = habit(&store, &["complete", "Read", "--date", &ahead, "--force"]);
+    assert!(forced.status.success());
+
+    let out = json(&habit(&store, &["doctor", "--fix", "--format", "json"]));
+    let future: Vec<&Value> = out
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|i| i["kind"] == "future_completions")
+        .collect();
+    assert_eq!(future.len(), 1);
+    assert_eq!(future[0]["fixed"], false);
+    let text = stdout(&habit(&store, &["doctor", "--fix"]));
+    assert!(text.contains("kept: --fix leaves these alone"), "{}", text);
+    assert!(!text.contains("Run `habit doctor --fix`"), "{}", text);
+    // the fixture's other issues were repaired, the forced day kept
+    assert!(fs::read_to_string(&store).unwrap().contains(&ahead));
+}
+
+#[test]
+fn errors_are_json_on_stderr() {
+    let store = stage();
+    let errors: Vec<Value> = ["json", "csv"]
+        .iter()
+        .map(|format| {
+            let out = habit(&store, &["complete", "Nope", "--format", format]);
+            assert_eq!(out.status.code(), Some(1));
+            assert!(out.stdout.is_empty());
+            serde_json::from_slice(&out.stderr).unwrap()
+        })
+        .collect();
+    assert_eq!(errors[0], errors[1]);
+    insta::assert_json_snapshot!(errors[0]);
+}
+
+#[test]
+fn text_is_the_default() {
+    let store = stage();
+    let out = stdout(&habit(
+        &store,
+        &["uncomplete", "Read", "--date", "2025-01-05"],
+    ));
+    assert_eq!(out, "↩️  Removed completion: 'Read' (2025-01-05)\n");
+}
+
+#[test]
+fn uncomplete_on_an_empty_day_fails() {
+    let store = stage();
+    let before = fs::read(&store).unwrap();
+    let out = habit(
+        &store,
+        &[
+            "uncomplete",
+            "Read",
+            "--date",
+            "2025-01-04",
+            "--format",
+            "json",
+        ],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let error: Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(error["error"]["code"], "not_completed");
+    assert_eq!(fs::read(&store).unwrap(), before);
+}
+
+#[test]
+fn today_check_in_saves_once() {
+    let store = stage();
+    let due = json(&habit(&store, &["today", "--format", "json"]));
+    let names: Vec<&str> = due
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|h| h["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Read", "Water"]);
+
+    let out = habit_with_input(
+        &store,
+        &["today", "-i", "--format", "json"],
+        "1 2\n2\n2\n\n",
+    );
+    let ticked = json(&out);
+    assert_eq!(ticked[0]["name"], "Read");
+    assert_eq!(ticked[1]["name"], "Water");
+
+    let log = json(&habit(&store, &["log", "--format", "json"]));
+    assert_eq!(log[0]["command"], "--tz UTC today -i --format json");
+    assert_eq!(log[0]["events"].as_array().unwrap().len(), 2);
+    assert_eq!(log[1]["kind"], "baseline");
+
+    let finished = habit_with_input(&store, &["today", "-i"], "q\n");
+    assert!(stdout(&finished).contains("Everything due today is already done"));
+}
+
+#[test]
+fn calendar_heatmap() {
+    let store = stage();
+    let text = stdout(&habit(&store, &["calendar", "--year", "2025", "--ascii"]));
+    insta::assert_snapshot!(text);
+
+    let days = json(&habit(
+        &store,
+        &["calendar", "Water", "--year", "2025", "--format", "json"],
+    ));
+    let days = days.as_array().unwrap();
+    assert_eq!(days.len(), 365);
+    assert_eq!(
+        days[..3],
+        [
+            serde_json::json!({"date": "2025-01-01", "ratio": 1.0, "level": 4}),
+            serde_json::json!({"date": "2025-01-02", "ratio": 0.375, "level": 2}),
+            serde_json::json!({"date": "2025-01-03", "ratio": 0.0, "level": 0}),
+        ]
+    );
+}
+
+#[test]
+fn report_renders_markdown_and_html() {
+    let store = stage();
+    let markdown = stdout(&habit(
+        &store,
+        &["report", "--week", "2025-W01", "--markdown"],
+    ));
+    insta::assert_snapshot!(markdown);
+    let html = stdout(&habit(&store, &["report", "--month", "2025-01", "--html"]));
+    insta::assert_snapshot!(html);
+
+    let rows = json(&habit(
+        &store,
+        &["report", "--week", "2025-W02", "--format", "json"],
+    ));
+    let journal = &rows.as_array().unwrap()[2];
+    assert_eq!(journal["name"], "Journal");
+    assert_eq!(journal["period"], "2025-W02");
+    assert_eq!(journal["completed"], 1);
+
+    let out = habit(&store, &["report", "--week", "2025-W60"]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("invalid period: 2025-W60"));
+}
+
+#[test]
+fn export_round_trips_through_import() {
+    let store = stage();
+    let dir = store.parent().unwrap();
+    let exported = stdout(&habit(&store, &["export"]));
+    insta::assert_snapshot!(exported);
+    let file = dir.join("all.csv");
+    fs::write(&file, &exported).unwrap();
+    let file = file.to_str().unwrap();
+
+    let copy = dir.join("copy.json");
+    let imported = json(&habit(&copy, &["import", file, "--format", "json"]));
+    assert_eq!(
+        imported["created"],
+        serde_json::json!(["Read", "Water", "Journal"])
+    );
+    assert_eq!(imported["added"], 8);
+    // the fixture logs Journal twice on 2025-01-10
+    assert_eq!(imported["duplicates"], 1);
+    let listed = |store: &Path| json(&habit(store, &["list", "--all", "--format", "json"]));
+    assert_eq!(listed(&copy), listed(&store));
+
+    let again = json(&habit(
+        &copy,
+        &["import", file, "--merge", "id", "--format", "json"],
+    ));
+    assert_eq!(again["created"], serde_json::json!([]));
+    assert_eq!(
+        (again["added"].as_u64(), again["duplicates"].as_u64()),
+        (Some(0), Some(9))
+    );
+
+    let (habits, completions) = (dir.join("habits.tsv"), dir.join("completions.tsv"));
+    let (habits, completions) = (habits.to_str().unwrap(), completions.to_str().unwrap());
+    stdout(&habit(
+        &store,
+        &["export", habits, "--completions", completions],
+    ));
+    let split = dir.join("split.json");
+    stdout(&habit(&split, &["import", habits, completions]));
+    assert_eq!(listed(&split), listed(&store));
+}
+
+#[test]
+fn pause_resume_and_vacation() {
+    let store = stage();
+    let paused = json(&habit(
+        &store,
+        &["pause", "Read", "--from", "-2d", "--format", "json"],
+    ));
+    assert_eq!(paused["paused"], true);
+    assert_eq!(paused["pauses"][0]["until"], Value::Null);
+    let listed: Value =
+        serde_json::from_str(&stdout(&habit(&store, &["list", "--format", "json"]))).unwrap();
+    assert_eq!(listed[0]["paused"], true);
+    assert_eq!(listed[0]["due_today"], false);
+
+    let out = habit(&store, &["pause", "Read", "--format", "json"]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("\"already_paused\""));
+    let resumed = json(&habit(&store, &["resume", "Read", "--format", "json"]));
+    assert_eq!(resumed["paused"], false);
+    let out = habit(&store, &["resume", "Read", "--format", "json"]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("\"not_paused\""));
+
+    // a pause running into a later one is refused, one ending before it is not
+    let pause = |from: &str, to: &str| {
+        habit(
+            &store,
+            &[
+                "pause", "Water", "--from", from, "--until", to, "--format", "json",
+            ],
+        )
+    };
+    assert!(pause("2025-01-05", "2025-01-07").status.success());
+    let out = pause("2025-01-04", "2025-01-05");
+    assert_eq!(out.status.code(), Some(1));
+    let err: Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(err["error"]["code"], "already_paused");
+    assert!(pause("2025-01-02", "2025-01-04").status.success());
+    let water = json(&pause("2025-01-08", "2025-01-08"));
+    assert_eq!(water["pauses"].as_array().unwrap().len(), 3);
+
+    let away = json(&habit(
+        &store,
+        &[
+            "vacation",
+            "add",
+            "--from",
+            "-1d",
+            "--to",
+            "2099-01-01",
+            "--format",
+            "json",
+        ],
+    ));
+    assert_eq!(away["habits"], 3);
+    // habits added during a vacation join it
+    stdout(&habit(&store, &["add", "Stretch"]));
+    let vacations = json(&habit(&store, &["vacation", "list", "--format", "json"]));
+    assert_eq!(vacations.as_array().unwrap().len(), 1);
+    assert_eq!(vacations[0]["habits"], 4);
+    let today = stdout(&habit(&store, &["today"]));
+    assert!(today.contains("Nothing due today"), "{}", today);
+
+    stdout(&habit(&store, &["vacation", "remove", "-1d"]));
+    let vacations = json(&habit(&store, &["vacation", "list", "--format", "json"]));
+    assert_eq!(vacations, serde_json::json!([]));
+}
+
+#[test]
+fn export_ics_calendar() {
+    let store = stage();
+    let feed = stdout(&habit(&store, &["export", "--format", "ics"]));
+    assert!(feed.ends_with("END:VCALENDAR\r\n"));
+    assert!(
+        feed.split("\r\n")
+            .all(|l| l.len() <= 75 && !l.contains('\n'))
+    );
+    let feed: String = feed
+        .replace("\r\n", "\n")
+        .lines()
+        .map(|l| match l.strip_prefix("DTSTAMP:") {
+            Some(_) => "DTSTAMP:[redacted]\n".to_string(),
+            None => format!("{}\n", l),
+        })
+        .collect();
+    insta::assert_snapshot!(feed);
+
+    // a file export carries the same UIDs, so a subscribed calendar updates
+    let file = store.parent().unwrap().join("habits.ics");
+    let printed = stdout(&habit(&store, &["export", file.to_str().unwrap()]));
+    assert!(printed.starts_with("📤 Exported 2 habits and 9 completions to "));
+    let uids = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter(|l| l.starts_with("UID:"))
+            .map(|l| l.trim_end().to_string())
+            .collect()
+    };
+    assert_eq!(uids(&fs::read_to_string(&file).unwrap()), uids(&feed));
+
+    let out = habit(&store, &["list", "--format", "ics"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unsupported output format: ics"));
+}
+
+#[test]
+fn import_maps_columns_and_previews_with_dry_run() {
+    let store = stage();
+    let file = store.parent().unwrap().join("loop.csv");
+    fs::write(
+        &file,
+        "Habit Name,Day,Notes\nRead,2025-01-04,short chapter\nRead,2025-01-05,\nStretch,2025-01-05,\n",
+    )
+    .unwrap();
+    let file = file.to_str().unwrap();
+    let mut args = vec![
+        "import",
+        file,
+        "--map",
+        "Habit Name=habit",
+        "--map",
+        "Day=date",
+        "--map",
+        "notes=note",
+    ];
+    let before = fs::read(&store).unwrap();
+    args.push("--dry-run");
+    let preview = stdout(&habit(&store, &args));
+    assert_eq!(
+        preview,
+        format!(
+            "Importing {} would:\n  + new habit 'Stretch'\n  = existing habit 'Read'\n  2 completions added, 1 skipped as already logged\nRun again without --dry-run to save.\n",
+            file
+        )
+    );
+    assert_eq!(fs::read(&store).unwrap(), before);
+
+    args.pop();
+    stdout(&habit(&store, &args));
+    let rows = json(&habit(&store, &["export", "--format", "json"]));
+    let rows = rows.as_array().unwrap();
+    let noted: Vec<&Value> = rows.iter().filter(|r| !r["note"].is_null()).collect();
+    assert_eq!(noted.len(), 1);
+    assert_eq!(noted[0]["habit"], "Read");
+    assert_eq!(noted[0]["date"], "2025-01-04");
+    assert_eq!(noted[0]["note"], "short chapter");
+    assert!(rows.iter().any(|r| r["habit"] == "Stretch"));
+
+    let out = habit(&store, &["import", file]);
+    assert!(String::from_utf8_lossy(&out.stderr).contains("no habit or habit_id column"));
+}
@@ -0,0 +1,203 @@
+#![cfg(feature = "sqlite")]
+
+use habit::models::habit::{Completion, Habit, Pause};
+use habit::models::schedule::Schedule;
+use habit::storage::json_storage::{HabitStore, JsonFileRepository};
+use habit::storage::repository::HabitRepository;
+use habit::storage::sqlite_storage::SqliteRepository;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A fresh directory holding a copy of `fixture` as `habits.json`.
+fn stage(fixture: &str) -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("habit-sqlite-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("habits.json");
+    fs::copy(Path::new("tests/fixtures").join(fixture), &path).unwrap();
+    (dir, path)
+}
+
+fn habit(storage: &str, config: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_habit"))
+        .args(["--tz", "UTC"])
+        .args(args)
+        .env("HABIT_STORAGE", storage)
+        .env("HABIT_CONFIG", config)
+        .output()
+        .unwrap()
+}
+
+fn at(s: &str) -> chrono::DateTime<chrono::Utc> {
+    s.parse().unwrap()
+}
+
+#[test]
+fn json_round_trips_through_sqlite() {
+    let (dir, path) = stage("output_store.json");
+    let mut store = HabitStore::load_from(&path).unwrap();
+    // sub-second times whose text sorts before the whole second they follow
+    let mut late = Habit::new("Stretch".into(), None, "mon,thu".parse().unwrap());
+    late.created_at = at("2025-01-01T08:00:00Z");
+    late.completions = vec![
+        Completion::new(at("2025-01-02T12:00:00Z")),
+        Completion::new(at("2025-01-02T12:00:00.5Z")),
+        Completion::new(at("2025-01-03T12:00:00.123456789Z")),
+    ];
+    late.pauses = vec![
+        Pause {
+            from: "2025-01-06".parse().unwrap(),
+            until: Some("2025-01-07".parse().unwrap()),
+            vacation: false,
+        },
+        Pause {
+            from: "2025-02-01".parse().unwrap(),
+            until: None,
+            vacation: true,
+        },
+    ];
+    store.habits.push(late);
+    store.settings.timezone = Some("Asia/Kolkata".parse().unwrap());
+
+    let mut sqlite = SqliteRepository::open(&dir.join("habits.db")).unwrap();
+    sqlite.save(&store).unwrap();
+    assert_eq!(sqlite.load().unwrap(), store);
+    // a reopened file reads the same
+    drop(sqlite);
+    let sqlite = SqliteRepository::open(&dir.join("habits.db")).unwrap();
+    assert_eq!(sqlite.load().unwrap(), store);
+
+    let mut json = JsonFileRepository::new(dir.join("back.json"));
+    json.save(&sqlite.load().unwrap()).unwrap();
+    assert_eq!(json.load().unwrap(), store);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn storage_migrate_upgrades_a_v1_file_into_sqlite_and_back() {
+    let (dir, path) = stage("store_v1.json");
+    let config = dir.join("habits.toml");
+    let db = dir.join("habits.db");
+    let out = habit(
+        path.to_str().unwrap(),
+        &config,
+        &["storage", "migrate", "--to", "sqlite", "--format", "json"],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let view: Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(view["habits"], 3);
+
+    // loading the JSON store upgraded it in place on the way
+    let upgraded = HabitStore::load_from(&path).unwrap();
+    assert_eq!(
+        upgraded.habits[1].schedule,
+        Schedule::TimesPerWeek(3),
+        "v1 target_frequency"
+    );
+    let sqlite = SqliteRepository::open(&db).unwrap();
+    assert_eq!(sqlite.schema_version().unwrap(), 4);
+    assert_eq!(sqlite.load().unwrap(), upgraded);
+    drop(sqlite);
+
+    let back = dir.join("back.json");
+    let out = habit(
+        &format!("sqlite://{}", db.display()),
+        &config,
+        &[
+            "storage",
+            "migrate",
+            "--to",
+            "json",
+            "--path",
+            back.to_str().unwrap(),
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(HabitStore::load_from(&back).unwrap(), upgraded);
+
+    // a target holding habits is only replaced with --force
+    let out = habit(
+        path.to_str().unwrap(),
+        &config,
+        &["storage", "migrate", "--to", "sqlite"],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn delete_removes_a_habit_with_its_rows() {
+    let dir = std::env::temp_dir().join(format!("habit-sqlite-{}", uuid::Uuid::new_v4()));
+    let db = dir.join("habits.db");
+    let mut repo = SqliteRepository::open(&db).unwrap();
+    let lock = repo.lock().unwrap();
+    let mut habits = Vec::new();
+    for name in ["Read", "Run", "Swim"] {
+        let mut habit = Habit::new(name.into(), None, Schedule::Daily);
+        habit.completions = vec![Completion::new(at("2025-01-02T12:00:00Z"))];
+        habit.pauses = vec![Pause {
+            from: "2025-01-03".parse().unwrap(),
+            until: None,
+            vacation: false,
+        }];
+        repo.insert(lock.as_ref(), habit.clone()).unwrap();
+        habits.push(habit);
+    }
+
+    assert_eq!(repo.delete(lock.as_ref(), habits[1].id).unwrap(), habits[1]);
+    assert!(repo.delete(lock.as_ref(), habits[1].id).is_err());
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    for table in ["completions", "pauses"] {
+        let left: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE habit_id = ?1", table),
+                [habits[1].id.to_string()],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(left, 0, "{}", table);
+    }
+
+    // a habit added afterwards still goes last
+    let walk = Habit::new("Walk".into(), None, Schedule::Daily);
+    repo.insert(lock.as_ref(), walk).unwrap();
+    let names: Vec<String> = repo
+        .load()
+        .unwrap()
+        .habits
+        .into_iter()
+        .map(|h| h.name)
+        .collect();
+    assert_eq!(names, ["Read", "Swim", "Walk"]);
+    drop(lock);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn recover_does_not_salvage_a_database() {
+    let dir = std::env::temp_dir().join(format!("habit-sqlite-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let storage = format!("sqlite://{}", dir.join("habits.db").display());
+    let config = dir.join("habits.toml");
+    let out = habit(&storage, &config, &["add", "Read"]);
+    assert!(out.status.success());
+
+    let out = habit(
+        &storage,
+        &config,
+        &["recover", "--source", "salvage", "--format", "json"],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let error: Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(error["error"]["code"], "backend_unavailable");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,465 @@
+use habit::error::HabitError;
+use habit::models::habit::Habit;
+use habit::models::schedule::Schedule;
+use habit::storage::backup::{BackupPolicy, Backups};
+use habit::storage::journal::{EntryKind, Event, History, Journal};
+use habit::storage::json_storage::{self, HabitStore, JsonFileRepository};
+use habit::storage::lock::StoreLock;
+use habit::storage::recovery::{self, RecoverySource};
+use habit::storage::repository::{HabitRepository, MemoryRepository};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::Duration;
+
+/// A fresh directory and the store path inside it.
+fn stage() -> (PathBuf, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("habit-storage-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("habits.json");
+    (dir, path)
+}
+
+fn habit(store: &Path, args: &[&str]) -> Output {
+    let out = Command::new(env!("CARGO_BIN_EXE_habit"))
+        .args(["--tz", "UTC"])
+        .args(args)
+        .env("HABIT_STORAGE", store)
+        .env("HABIT_CONFIG", store.with_extension("toml"))
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    out
+}
+
+fn names(store: &HabitStore) -> Vec<&str> {
+    store.habits.iter().map(|h| h.name.as_str()).collect()
+}
+
+/// Runs insert, update and delete against `repo`, holding its lock throughout.
+fn exercise(repo: &mut dyn HabitRepository) {
+    let lock = repo.lock().unwrap();
+    let read = Habit::new("Read".into(), None, Schedule::Daily);
+    let mut run = Habit::new("Run".into(), None, Schedule::TimesPerWeek(3));
+    repo.insert(lock.as_ref(), read.clone()).unwrap();
+    repo.insert(lock.as_ref(), run.clone()).unwrap();
+    assert!(matches!(
+        repo.insert(lock.as_ref(), read.clone()),
+        Err(HabitError::AlreadyExists(_))
+    ));
+
+    run.name = "Jog".into();
+    repo.update(lock.as_ref(), &run).unwrap();
+    assert_eq!(repo.find("jog").unwrap().unwrap().id, run.id);
+    assert!(repo.find("Run").unwrap().is_none());
+    let stranger = Habit::new("Swim".into(), None, Schedule::Daily);
+    assert!(matches!(
+        repo.update(lock.as_ref(), &stranger),
+        Err(HabitError::NotFound(_))
+    ));
+
+    assert_eq!(repo.delete(lock.as_ref(), read.id).unwrap().name, "Read");
+    assert!(matches!(
+        repo.delete(lock.as_ref(), read.id),
+        Err(HabitError::NotFound(_))
+    ));
+    assert_eq!(names(&repo.load().unwrap()), ["Jog"]);
+}
+
+#[test]
+fn memory_repository_inserts_updates_and_deletes() {
+    let mut repo = MemoryRepository::default();
+    exercise(&mut repo);
+    assert_eq!(names(repo.store()), ["Jog"]);
+}
+
+#[test]
+fn json_repository_writes_under_the_held_lock() {
+    let (dir, path) = stage();
+    // a write taking a second lock of its own would time out here
+    let mut repo = JsonFileRepository::new(&path)
+        .with_backup_policy(BackupPolicy {
+            keep: 0,
+            daily: 0,
+            weekly: 0,
+        })
+        .with_lock_timeout(Duration::from_millis(100));
+    exercise(&mut repo);
+    assert_eq!(names(&HabitStore::load_from(&path).unwrap()), ["Jog"]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn json_repository_writes_without_a_lock_wait_for_one_held_elsewhere() {
+    let (dir, path) = stage();
+    let repo = |wait| {
+        JsonFileRepository::new(&path)
+            .with_backup_policy(BackupPolicy {
+                keep: 0,
+                daily: 0,
+                weekly: 0,
+            })
+            .with_lock_timeout(wait)
+    };
+    let held = repo(Duration::ZERO).lock().unwrap();
+    let read = Habit::new("Read".into(), None, Schedule::Daily);
+
+    assert!(matches!(
+        repo(Duration::from_millis(100)).insert(None, read.clone()),
+        Err(HabitError::Locked(_))
+    ));
+    assert!(!path.exists());
+
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        drop(held);
+    });
+    let started = std::time::Instant::now();
+    repo(Duration::from_secs(5)).insert(None, read).unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(150));
+    release.join().unwrap();
+    assert_eq!(names(&HabitStore::load_from(&path).unwrap()), ["Read"]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_second_lock_on_the_same_store_fails_until_the_first_is_dropped() {
+    let (dir, path) = stage();
+    let wait = Duration::from_millis(100);
+    let held = StoreLock::acquire(&path, wait).unwrap();
+    assert_eq!(held.path(), dir.join("habits.json.lock"));
+    match StoreLock::acquire(&path, wait) {
+        Err(HabitError::Locked(what)) => assert!(what.ends_with("habits.json.lock")),
+        other => panic!("expected Locked, got {:?}", other),
+    }
+    // so does a repository on the same file
+    let repo = JsonFileRepository::new(&path).with_lock_timeout(wait);
+    assert!(matches!(repo.lock(), Err(HabitError::Locked(_))));
+
+    drop(held);
+    let again = StoreLock::acquire(&path, wait).unwrap();
+    assert!(matches!(
+        StoreLock::acquire(&path, Duration::ZERO),
+        Err(HabitError::Locked(_))
+    ));
+    drop(again);
+    assert!(repo.lock().unwrap().is_some());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saves_rotate_backups_of_the_replaced_store() {
+    let (dir, path) = stage();
+    let mut repo = JsonFileRepository::new(&path).with_backup_policy(BackupPolicy {
+        keep: 3,
+        daily: 0,
+        weekly: 0,
+    });
+    let mut store = HabitStore::default();
+    for name in ["A", "B", "C", "D", "E", "F"] {
+        store
+            .habits
+            .push(Habit::new(name.into(), None, Schedule::Daily));
+        repo.save(&store).unwrap();
+    }
+    // the first save had nothing to back up; the oldest two were pruned
+    let backups = Backups::for_store(&path);
+    let kept: Vec<Vec<String>> = backups
+        .list()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            let store = e.load().unwrap();
+            names(&store).iter().map(|n| n.to_string()).collect()
+        })
+        .collect();
+    assert_eq!(
+        kept,
+        [
+            vec!["A", "B", "C", "D", "E"],
+            vec!["A", "B", "C", "D"],
+            vec!["A", "B", "C"],
+        ]
+    );
+    assert_eq!(
+        backups.find("1").unwrap().path,
+        backups.list().unwrap()[0].path
+    );
+    assert!(backups.find("4").is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prune_keeps_the_newest_and_one_per_recent_day_and_week() {
+    let (dir, path) = stage();
+    let backups = Backups::for_store(&path);
+    fs::create_dir_all(backups.dir()).unwrap();
+    let data = serde_json::to_vec(&HabitStore::default()).unwrap();
+    let stamps = [
+        "20250113T180000.000Z",
+        "20250113T100000.000Z",
+        "20250112T200000.000Z",
+        "20250112T090000.000Z",
+        "20250111T120000.000Z",
+        "20250108T120000.000Z",
+        "20250107T120000.000Z",
+        "20250103T190000.000Z",
+        "20250103T080000.000Z",
+        "20241225T120000.000Z",
+    ];
+    for stamp in stamps {
+        fs::write(backups.dir().join(format!("habits-{}.json", stamp)), &data).unwrap();
+    }
+    let policy = BackupPolicy {
+        keep: 2,
+        daily: 3,
+        weekly: 3,
+    };
+    assert_eq!(backups.prune(&policy).unwrap(), 5);
+    let kept: Vec<String> = backups.list().unwrap().iter().map(|e| e.id()).collect();
+    assert_eq!(
+        kept,
+        [
+            // the newest two
+            "20250113T180000.000",
+            "20250113T100000.000",
+            // the newest of the 12th and 11th
+            "20250112T200000.000",
+            "20250111T120000.000",
+            // the newest of the week before; the 13th and 12th cover theirs
+            "20250103T190000.000",
+        ]
+    );
+    // pruning again removes nothing
+    assert_eq!(backups.prune(&policy).unwrap(), 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn backup_restore_brings_back_the_earlier_store() {
+    let (dir, path) = stage();
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["add", "Run"]);
+    habit(&path, &["complete", "Run"]);
+    let current = HabitStore::load_from(&path).unwrap();
+
+    // backup 2 was taken before Run was added
+    let preview = habit(&path, &["backup", "restore", "2", "--format", "json"]);
+    let view: serde_json::Value = serde_json::from_slice(&preview.stdout).unwrap();
+    assert_eq!(view["applied"], false);
+    assert_eq!(view["changes"].as_array().unwrap().len(), 1);
+    assert_eq!(HabitStore::load_from(&path).unwrap(), current);
+
+    habit(&path, &["backup", "restore", "2", "--yes"]);
+    assert_eq!(names(&HabitStore::load_from(&path).unwrap()), ["Read"]);
+    // the store it replaced is now the newest backup, so the restore can be undone
+    let newest = Backups::for_store(&path).list().unwrap()[0].load().unwrap();
+    assert_eq!(newest, current);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+fn json_out(out: &Output) -> serde_json::Value {
+    serde_json::from_slice(&out.stdout).unwrap()
+}
+
+#[test]
+fn undo_and_redo_keep_the_journal_replayable() {
+    let (dir, path) = stage();
+    let journal = Journal::for_store(&path);
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["complete", "Read"]);
+    habit(&path, &["edit", "Read", "--name", "Books"]);
+    let edited = HabitStore::load_from(&path).unwrap();
+    assert_eq!(journal.replay().unwrap(), edited);
+
+    habit(&path, &["undo", "2"]);
+    let undone = HabitStore::load_from(&path).unwrap();
+    assert_eq!(names(&undone), ["Read"]);
+    assert!(undone.habits[0].completions.is_empty());
+    assert_eq!(journal.replay().unwrap(), undone);
+
+    habit(&path, &["redo"]);
+    let redone = HabitStore::load_from(&path).unwrap();
+    assert_eq!(redone.habits[0].completions.len(), 1);
+    assert_eq!(journal.replay().unwrap(), redone);
+    habit(&path, &["redo"]);
+    assert_eq!(HabitStore::load_from(&path).unwrap(), edited);
+    assert_eq!(journal.replay().unwrap(), edited);
+    let verify = json_out(&habit(&path, &["log", "--verify", "--format", "json"]));
+    assert_eq!(verify["consistent"], true);
+
+    let seqs: Vec<u64> = journal.entries().unwrap().iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, [1, 2, 3, 4, 5, 6, 7]);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn a_new_command_clears_what_could_be_redone() {
+    let (dir, path) = stage();
+    let journal = Journal::for_store(&path);
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["add", "Run"]);
+    habit(&path, &["undo"]);
+    assert_eq!(History::of(&journal.entries().unwrap()).undone, [2]);
+
+    habit(&path, &["add", "Swim"]);
+    let history = History::of(&journal.entries().unwrap());
+    assert_eq!(history.done, [1, 4]);
+    assert!(history.undone.is_empty());
+    let redo = json_out(&habit(&path, &["redo", "--format", "json"]));
+    assert_eq!(redo, serde_json::json!([]));
+    assert_eq!(
+        names(&HabitStore::load_from(&path).unwrap()),
+        ["Read", "Swim"]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn undo_conflicts_when_its_habit_is_gone() {
+    let (dir, path) = stage();
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["complete", "Read"]);
+    // the habit disappears behind the journal's back
+    let mut store = HabitStore::load_from(&path).unwrap();
+    let read = store.habits.remove(0);
+    store.save_to(&path).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_habit"))
+        .args(["undo", "--format", "json"])
+        .env("HABIT_STORAGE", &path)
+        .env("HABIT_CONFIG", path.with_extension("toml"))
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let error: serde_json::Value = serde_json::from_slice(&out.stderr).unwrap();
+    assert_eq!(error["error"]["code"], "journal_conflict");
+    assert_eq!(HabitStore::load_from(&path).unwrap(), store);
+
+    let event = Event::Completed {
+        habit: read.id,
+        name: read.name.clone(),
+        completions: read.completions.clone(),
+    };
+    assert!(matches!(
+        event.apply(&mut HabitStore::default()),
+        Err(HabitError::JournalConflict(_))
+    ));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn appends_number_entries_past_the_first_read_block() {
+    let (dir, path) = stage();
+    let journal = Journal::for_store(&path);
+    let long = "x".repeat(5000);
+    for i in 1..=3 {
+        let entry = journal
+            .append(&long, EntryKind::Command, Vec::new())
+            .unwrap();
+        assert_eq!(entry.seq, i);
+    }
+    // blank lines at the end are skipped
+    let mut data = fs::read(journal.path()).unwrap();
+    data.extend_from_slice(b"\n\n");
+    fs::write(journal.path(), data).unwrap();
+    assert_eq!(
+        journal
+            .append("short", EntryKind::Command, Vec::new())
+            .unwrap()
+            .seq,
+        4
+    );
+    assert_eq!(journal.entries().unwrap().len(), 4);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn recovery_prefers_temp_file_then_journal_then_backup() {
+    let (dir, path) = stage();
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["add", "Run"]);
+    habit(&path, &["complete", "Read"]);
+    let saved = HabitStore::load_from(&path).unwrap();
+    // a save cut short mid-write
+    let data = fs::read(&path).unwrap();
+    fs::write(&path, &data[..data.len() / 2]).unwrap();
+    assert!(matches!(
+        HabitStore::load_from(&path),
+        Err(HabitError::Corrupt(_))
+    ));
+    let sources = |path: &Path| -> Vec<RecoverySource> {
+        let (found, _) = recovery::candidates(path, true);
+        found.iter().map(|c| c.source).collect()
+    };
+
+    // a finished save that was never renamed into place
+    let tmp = json_storage::temp_path(&path);
+    let mut pending = saved.clone();
+    pending.habits.pop();
+    pending.save_to(&tmp).unwrap();
+    assert_eq!(
+        sources(&path),
+        [
+            RecoverySource::TempFile,
+            RecoverySource::Journal,
+            RecoverySource::Backup,
+            RecoverySource::Salvage,
+        ]
+    );
+    let (found, _) = recovery::candidates(&path, true);
+    assert_eq!(found[0].store, pending);
+    assert_eq!(found[1].store, saved);
+    // the newest backup is the store before the last save
+    assert_eq!(names(&found[2].store), ["Read", "Run"]);
+    assert!(found[2].store.habits[0].completions.is_empty());
+
+    fs::remove_file(&tmp).unwrap();
+    assert_eq!(
+        sources(&path),
+        [
+            RecoverySource::Journal,
+            RecoverySource::Backup,
+            RecoverySource::Salvage,
+        ]
+    );
+    let recovered = json_out(&habit(&path, &["recover", "--yes", "--format", "json"]));
+    assert_eq!(recovered["source"], "journal");
+    assert_eq!(HabitStore::load_from(&path).unwrap(), saved);
+
+    fs::write(&path, &data[..data.len() / 2]).unwrap();
+    fs::remove_file(Journal::for_store(&path).path()).unwrap();
+    assert_eq!(
+        sources(&path),
+        [RecoverySource::Backup, RecoverySource::Salvage]
+    );
+    let recovered = json_out(&habit(&path, &["recover", "--yes", "--format", "json"]));
+    assert_eq!(recovered["source"], "backup");
+    assert_eq!(
+        names(&HabitStore::load_from(&path).unwrap()),
+        ["Read", "Run"]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn salvage_keeps_the_habits_before_the_damage() {
+    let (dir, path) = stage();
+    habit(&path, &["add", "Read"]);
+    habit(&path, &["add", "Run"]);
+    let data = fs::read(&path).unwrap();
+    // cut inside the second habit
+    let cut = String::from_utf8_lossy(&data).find("\"Run\"").unwrap();
+    let salvaged = recovery::salvage(&data[..cut]);
+    assert_eq!(names(&salvaged.store), ["Read"]);
+    assert!(!salvaged.lost.is_empty());
+    // a database is never parsed as JSON
+    let (found, nothing) = recovery::candidates(&path, false);
+    assert!(found.iter().all(|c| c.source != RecoverySource::Salvage));
+    assert!(nothing.lost.is_empty());
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,143 @@
+#![cfg(feature = "tui")]
+
+use chrono::NaiveDate;
+use habit::models::timezone::Zone;
+use habit::storage::json_storage::HabitStore;
+use habit::storage::repository::{HabitRepository, MemoryRepository};
+use habit::tui::app::{App, Mode};
+use habit::tui::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+
+fn repo() -> MemoryRepository {
+    let data = fs::read("tests/fixtures/output_store.json").unwrap();
+    MemoryRepository::new(HabitStore::from_slice(&data).unwrap())
+}
+
+fn today() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 1, 6).unwrap()
+}
+
+fn press(app: &mut App, keys: &str) {
+    for c in keys.chars() {
+        app.handle(key(KeyCode::Char(c)));
+    }
+}
+
+fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+/// Renders the app into an in-memory buffer and returns its text.
+fn screen(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+    terminal.draw(|frame| ui::draw(frame, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    buffer
+        .content()
+        .chunks(buffer.area.width as usize)
+        .map(|row| {
+            row.iter()
+                .map(|c| c.symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn renders_list_calendar_and_stats() {
+    let mut repo = repo();
+    let app = App::new(&mut repo, Zone::default(), today()).unwrap();
+    insta::assert_snapshot!(screen(&app));
+}
+
+#[test]
+fn toggles_the_cursor_day_through_the_repository() {
+    let mut repo = repo();
+    let mut app = App::new(&mut repo, Zone::default(), today()).unwrap();
+    // Read was done on the 5th; the cursor starts on today, the 6th
+    app.handle(key(KeyCode::Char(' ')));
+    assert_eq!(app.status, "Completed 'Read' on 2025-01-06");
+    press(&mut app, "h ");
+    assert_eq!(app.status, "Cleared 'Read' on 2025-01-05");
+    press(&mut app, "ll");
+    app.handle(key(KeyCode::Enter));
+    assert_eq!(
+        app.status,
+        "2025-01-07 is in the future (use --force to log it anyway)"
+    );
+    assert!(screen(&app).contains("2025-01-07 is in the future"));
+    drop(app);
+
+    let days: Vec<String> = repo.load().unwrap().habits[0]
+        .completions
+        .iter()
+        .map(|c| c.at.date_naive().to_string())
+        .collect();
+    // today's completion is stamped with the real clock, like `habit complete`
+    assert_eq!(days.len(), 4);
+    assert_eq!(days[..3], ["2025-01-01", "2025-01-02", "2025-01-03"]);
+}
+
+#[test]
+fn edits_fields_in_the_form() {
+    let mut repo = repo();
+    let mut app = App::new(&mut repo, Zone::default(), today()).unwrap();
+    press(&mut app, "je");
+    assert!(matches!(app.mode, Mode::Form(_)));
+    assert!(screen(&app).contains("Edit habit"));
+    for _ in 0.."Water".len() {
+        app.handle(key(KeyCode::Backspace));
+    }
+    press(&mut app, "Hydrate");
+    // jump to the target field and make it unparseable
+    app.handle(key(KeyCode::Tab));
+    app.handle(key(KeyCode::Tab));
+    app.handle(key(KeyCode::Tab));
+    press(&mut app, "x");
+    app.handle(key(KeyCode::Enter));
+    assert!(app.status.starts_with("invalid amount: 8x"));
+    assert!(matches!(app.mode, Mode::Form(_)));
+
+    app.handle(key(KeyCode::Backspace));
+    app.handle(key(KeyCode::Tab));
+    app.handle(key(KeyCode::Tab));
+    press(&mut app, "health");
+    app.handle(key(KeyCode::Enter));
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.status, "Saved 'Hydrate'");
+    drop(app);
+
+    let water = &repo.load().unwrap().habits[1];
+    assert_eq!(water.name, "Hydrate");
+    assert_eq!(water.goal.as_ref().unwrap().target, 8.0);
+    assert_eq!(water.tags, ["health"]);
+}
+
+#[test]
+fn adds_and_filters_habits() {
+    let mut repo = repo();
+    let mut app = App::new(&mut repo, Zone::default(), today()).unwrap();
+    press(&mut app, "nStretch");
+    app.handle(key(KeyCode::Enter));
+    assert_eq!(app.current().unwrap().name, "Stretch");
+
+    press(&mut app, "/wat");
+    app.handle(key(KeyCode::Enter));
+    let names: Vec<&str> = app.visible().iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["Water"]);
+    assert!(screen(&app).contains("Habits matching 'wat'"));
+
+    app.handle(key(KeyCode::Esc));
+    press(&mut app, "a");
+    assert_eq!(app.visible().len(), 4);
+    press(&mut app, "q");
+    assert!(app.quit);
+    drop(app);
+    assert_eq!(repo.load().unwrap().habits.len(), 4);
+}
@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use habit::error::HabitError;
+use habit::utils::{day_range, parse_day};
+
+fn day(d: &str) -> NaiveDate {
+    d.parse().unwrap()
+}
+
+#[test]
+fn parse_day_accepts_names_offsets_and_dates() {
+    let today = day("2025-03-10");
+    assert_eq!(parse_day("today", today).unwrap(), today);
+    assert_eq!(parse_day(" Yesterday ", today).unwrap(), day("2025-03-09"));
+    assert_eq!(parse_day("-0d", today).unwrap(), today);
+    assert_eq!(parse_day("-3d", today).unwrap(), day("2025-03-07"));
+    assert_eq!(parse_day("-2W", today).unwrap(), day("2025-02-24"));
+    assert_eq!(parse_day("2024-02-29", today).unwrap(), day("2024-02-29"));
+}
+
+#[test]
+fn parse_day_rejects_bad_input() {
+    let today = day("2025-03-10");
+    for bad in [
+        "",
+        "-",
+        "-d",
+        "-w",
+        "-3",
+        "-3m",
+        "-x1d",
+        "+3d",
+        "tomorrow",
+        "2025-02-30",
+        "10/03/2025",
+        // too far back for a date, and too many weeks to count in days
+        "-9999999999d",
+        "-9999999999999999999w",
+        "-99999999999999999999d",
+        // a multibyte last character must not split mid-character
+        "-2é",
+        "-é",
+        "-2日",
+    ] {
+        assert!(
+            matches!(parse_day(bad, today), Err(HabitError::InvalidDate(ref s)) if s == bad),
+            "{:?} parsed",
+            bad
+        );
+    }
+}
+
+#[test]
+fn day_range_is_inclusive_and_ordered() {
+    assert_eq!(
+        day_range(day("2025-02-27"), day("2025-03-01")).unwrap(),
+        [day("2025-02-27"), day("2025-02-28"), day("2025-03-01")]
+    );
+    assert_eq!(
+        day_range(day("2025-03-01"), day("2025-03-01")).unwrap(),
+        [day("2025-03-01")]
+    );
+    assert!(day_range(day("2025-03-02"), day("2025-03-01")).is_err());
+}
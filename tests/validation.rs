@@ -0,0 +1,234 @@
+use chrono::{DateTime, Duration, Utc};
+use habit::models::habit::{Completion, Goal, Habit, Pause};
+use habit::models::schedule::Schedule;
+use habit::models::timezone::Zone;
+use habit::storage::json_storage::HabitStore;
+use habit::storage::validation::{IssueKind, Severity};
+
+fn at(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+fn habit(name: &str, done: &[&str]) -> Habit {
+    let mut habit = Habit::new(name.into(), None, Schedule::Daily);
+    habit.created_at = at("2025-01-01T08:00:00Z");
+    habit.completions = done.iter().map(|d| Completion::new(at(d))).collect();
+    habit
+}
+
+fn store(habits: Vec<Habit>) -> HabitStore {
+    HabitStore {
+        habits,
+        ..HabitStore::default()
+    }
+}
+
+fn store_of(names: &[&str]) -> HabitStore {
+    store(names.iter().map(|n| habit(n, &[])).collect())
+}
+
+/// Checks that `store` shows exactly `kind`, that repairing it reports the
+/// same and leaves a clean store with every habit, and returns that store.
+fn repaired(mut store: HabitStore, kind: IssueKind) -> HabitStore {
+    let zone = Zone::default();
+    let found = store.validate(&zone);
+    assert_eq!(
+        found.iter().map(|i| i.kind).collect::<Vec<_>>(),
+        [kind],
+        "{:?}",
+        found
+    );
+    let count = store.habits.len();
+    assert_eq!(store.repair(&zone), found);
+    assert_eq!(store.validate(&zone), []);
+    assert_eq!(store.habits.len(), count);
+    store
+}
+
+#[test]
+fn duplicate_ids_get_a_new_id() {
+    let first = habit("Read", &[]);
+    let mut second = habit("Run", &[]);
+    second.id = first.id;
+    let fixed = repaired(store(vec![first.clone(), second]), IssueKind::DuplicateId);
+    assert_eq!(fixed.habits[0].id, first.id);
+    assert_ne!(fixed.habits[1].id, first.id);
+}
+
+#[test]
+fn duplicate_names_get_a_number() {
+    let fixed = repaired(
+        store(vec![
+            habit("Read", &[]),
+            habit("read", &[]),
+            habit("Read (2)", &[]),
+        ]),
+        IssueKind::DuplicateName,
+    );
+    let names: Vec<&str> = fixed.habits.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(names, ["Read", "read (3)", "Read (2)"]);
+}
+
+#[test]
+fn names_clash_exactly_when_lookups_cannot_tell_them_apart() {
+    // lookups fold ASCII case only, so these are two habits
+    let store = store(vec![habit("Ärger", &[]), habit("ärger", &[])]);
+    assert!(store.find_by_ident("ärger").unwrap().has_name("ärger"));
+    assert_eq!(store.validate(&Zone::default()), []);
+
+    let fixed = repaired(store_of(&["ärger", "äRGER"]), IssueKind::DuplicateName);
+    assert_eq!(fixed.habits[1].name, "äRGER (2)");
+    assert_eq!(fixed.find_by_ident("ÄRGER"), None);
+    assert_eq!(fixed.find_by_ident("äRGER (2)").unwrap().name, "äRGER (2)");
+}
+
+#[test]
+fn empty_names_are_named_after_the_id() {
+    let fixed = repaired(store(vec![habit("  ", &[])]), IssueKind::EmptyName);
+    let habit = &fixed.habits[0];
+    assert_eq!(
+        habit.name,
+        format!("Untitled {}", &habit.id.to_string()[..8])
+    );
+}
+
+#[test]
+fn unsorted_completions_are_sorted() {
+    let fixed = repaired(
+        store(vec![habit(
+            "Read",
+            &["2025-01-03T12:00:00Z", "2025-01-02T12:00:00Z"],
+        )]),
+        IssueKind::UnsortedCompletions,
+    );
+    assert!(fixed.habits[0].completions.is_sorted_by_key(|c| c.at));
+}
+
+#[test]
+fn duplicate_completions_keep_the_first_of_each_day() {
+    let fixed = repaired(
+        store(vec![habit(
+            "Read",
+            &[
+                "2025-01-02T08:00:00Z",
+                "2025-01-02T20:00:00Z",
+                "2025-01-03T12:00:00Z",
+            ],
+        )]),
+        IssueKind::DuplicateCompletions,
+    );
+    let kept: Vec<DateTime<Utc>> = fixed.habits[0].completions.iter().map(|c| c.at).collect();
+    assert_eq!(
+        kept,
+        [at("2025-01-02T08:00:00Z"), at("2025-01-03T12:00:00Z")]
+    );
+
+    // a measurable habit logs several amounts a day; only exact repeats go
+    let mut water = habit("Water", &[]);
+    water.goal = Some(Goal {
+        target: 8.0,
+        unit: "glasses".into(),
+    });
+    for (when, amount) in [
+        ("2025-01-02T08:00:00Z", 2.0),
+        ("2025-01-02T08:00:00Z", 2.0),
+        ("2025-01-02T12:00:00Z", 2.0),
+    ] {
+        water.completions.push(Completion {
+            amount: Some(amount),
+            ..Completion::new(at(when))
+        });
+    }
+    let fixed = repaired(store(vec![water]), IssueKind::DuplicateCompletions);
+    assert_eq!(fixed.habits[0].completions.len(), 2);
+}
+
+#[test]
+fn invalid_amounts_are_dropped() {
+    let mut water = habit("Water", &[]);
+    water.goal = Some(Goal {
+        target: 8.0,
+        unit: "glasses".into(),
+    });
+    for amount in [2.0, 0.0, -1.0, f64::NAN, f64::INFINITY] {
+        water.completions.push(Completion {
+            amount: Some(amount),
+            ..Completion::new(at("2025-01-02T12:00:00Z"))
+        });
+    }
+    let fixed = repaired(store(vec![water]), IssueKind::InvalidAmounts);
+    assert_eq!(fixed.habits[0].completions.len(), 1);
+    assert_eq!(fixed.habits[0].completions[0].amount, Some(2.0));
+    assert_eq!(IssueKind::InvalidAmounts.severity(), Severity::Error);
+}
+
+#[test]
+fn future_completions_are_reported_but_kept() {
+    // `complete --force` logs these on purpose
+    let zone = Zone::default();
+    let mut read = habit("Read", &["2025-01-02T12:00:00Z"]);
+    read.completions
+        .push(Completion::new(Utc::now() + Duration::days(2)));
+    let mut store = store(vec![read]);
+    let before = store.clone();
+    let found = store.validate(&zone);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].kind, IssueKind::FutureCompletions);
+    assert_eq!(IssueKind::FutureCompletions.repair(), None);
+    assert_eq!(store.repair(&zone), []);
+    assert_eq!(store, before);
+    assert_eq!(store.validate(&zone), found);
+}
+
+#[test]
+fn completions_before_creation_move_it_back() {
+    let fixed = repaired(
+        store(vec![habit(
+            "Read",
+            &["2024-12-30T12:00:00Z", "2025-01-02T12:00:00Z"],
+        )]),
+        IssueKind::CompletionsBeforeCreated,
+    );
+    assert_eq!(fixed.habits[0].created_at, at("2024-12-30T12:00:00Z"));
+    assert_eq!(fixed.habits[0].completions.len(), 2);
+}
+
+#[test]
+fn backwards_pauses_are_dropped() {
+    let mut read = habit("Read", &[]);
+    let good = Pause {
+        from: "2025-01-05".parse().unwrap(),
+        until: Some("2025-01-06".parse().unwrap()),
+        vacation: false,
+    };
+    read.pauses = vec![
+        good,
+        Pause {
+            from: "2025-01-09".parse().unwrap(),
+            until: Some("2025-01-08".parse().unwrap()),
+            vacation: false,
+        },
+    ];
+    let fixed = repaired(store(vec![read]), IssueKind::InvalidPauses);
+    assert_eq!(fixed.habits[0].pauses, [good]);
+}
+
+#[test]
+fn a_store_with_every_problem_repairs_all_but_future_completions() {
+    let zone = Zone::default();
+    let first = habit("Read", &["2025-01-03T12:00:00Z", "2025-01-02T12:00:00Z"]);
+    let mut twin = habit("READ", &["2024-12-01T12:00:00Z"]);
+    twin.id = first.id;
+    let mut blank = habit("", &["2025-01-02T08:00:00Z", "2025-01-02T09:00:00Z"]);
+    blank
+        .completions
+        .push(Completion::new(Utc::now() + Duration::days(1)));
+    let mut broken = store(vec![first, twin, blank]);
+
+    let found = broken.validate(&zone);
+    assert_eq!(found.len(), 7);
+    assert_eq!(broken.repair(&zone).len(), 6);
+    let left: Vec<IssueKind> = broken.validate(&zone).iter().map(|i| i.kind).collect();
+    assert_eq!(left, [IssueKind::FutureCompletions]);
+    assert_eq!(broken.habits.len(), 3);
+}